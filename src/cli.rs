@@ -0,0 +1,68 @@
+use {
+    log::LogLevelFilter,
+    std::path::PathBuf,
+    structopt::StructOpt,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "hadlock", about = "A tiling and floating window manager for X")]
+pub struct Args {
+    /// Config file to use instead of hadlock.toml, hadlock.ron or hadlock.json in $XDG_CONFIG_HOME/hadlock
+    #[structopt(short, long, parse(from_os_str))]
+    pub config: Option<PathBuf>,
+
+    /// Config file, same as --config. Kept for session files that pass it this way
+    #[structopt(parse(from_os_str), hidden = true)]
+    pub config_arg: Option<PathBuf>,
+
+    /// Checks the config and exits, with a non-zero status if it has errors
+    #[structopt(long)]
+    pub check_config: bool,
+
+    /// Prints the default config as JSON and exits
+    #[structopt(long)]
+    pub print_default_config: bool,
+
+    /// Writes the JSON config as TOML next to it and exits
+    #[structopt(long)]
+    pub convert: bool,
+
+    /// One of off, error, warn, info, debug and trace
+    #[structopt(long, default_value = "debug", parse(try_from_str = parse_log_level))]
+    pub log_level: LogLevelFilter,
+
+    /// Writes the log to this file as well as to stderr
+    #[structopt(long, parse(from_os_str))]
+    pub log_file: Option<PathBuf>,
+
+    /// Takes over from the window manager that is running
+    #[structopt(long)]
+    pub replace: bool,
+}
+
+impl Args {
+    pub fn config_path(&self) -> Option<PathBuf> {
+        self.config.clone().or_else(|| self.config_arg.clone())
+    }
+}
+
+fn parse_log_level(level: &str) -> Result<LogLevelFilter, String> {
+    level.parse().map_err(|_| format!("unknown log level {}", level))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn config_flag_wins_over_the_positional_path() {
+        let args = Args::from_iter(&["hadlock", "old.json", "--config", "new.json", "--replace"]);
+        assert_eq!(args.config_path(), Some(PathBuf::from("new.json")));
+        assert!(args.replace);
+        assert_eq!(args.log_level, LogLevelFilter::Debug);
+
+        let args = Args::from_iter(&["hadlock", "old.json", "--log-level", "warn"]);
+        assert_eq!(args.config_path(), Some(PathBuf::from("old.json")));
+        assert_eq!(args.log_level, LogLevelFilter::Warn);
+    }
+}
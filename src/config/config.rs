@@ -4,6 +4,7 @@ use serde::{self, Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     #[serde(rename = "decorate", default = "default_decorate")]
     pub decorate: bool,
@@ -28,6 +29,9 @@ pub struct Config {
         default = "default_focused_background_color"
     )]
     pub focused_background_color: Color,
+
+    #[serde(rename = "notRespondingColor", default = "default_not_responding_color")]
+    pub not_responding_color: Color,
     
     #[serde(rename = "outerGap", default = "default_outer_gap")]
     pub outer_gap: i32,
@@ -38,6 +42,15 @@ pub struct Config {
     #[serde(rename = "smartGaps", default = "default_smart_gaps")]
     pub smart_gaps: bool,
 
+    #[serde(rename = "snapThreshold", default = "default_snap_threshold")]
+    pub snap_threshold: i32,
+
+    #[serde(rename = "closeTimeout", default = "default_close_timeout")]
+    pub close_timeout: u64,
+
+    #[serde(rename = "pingInterval", default = "default_ping_interval")]
+    pub ping_interval: u64,
+
     #[serde(rename = "defaultLayout", default = "default_layout")]
     pub default_layout: LayoutTag,
 
@@ -79,6 +92,10 @@ fn default_focused_background_color() -> Color {
     Color::DefaultFocusedBackground
 }
 
+fn default_not_responding_color() -> Color {
+    Color::Red
+}
+
 fn default_outer_gap() -> i32 {
     0
 }
@@ -91,6 +108,18 @@ fn default_smart_gaps() -> bool {
     false
 }
 
+fn default_snap_threshold() -> i32 {
+    10
+}
+
+fn default_close_timeout() -> u64 {
+    5000
+}
+
+fn default_ping_interval() -> u64 {
+    10000
+}
+
 fn default_layout() -> LayoutTag {
     LayoutTag::Floating
 }
@@ -121,9 +150,13 @@ impl Default for Config {
             border_color: default_border_color(),
             background_color: default_background_color(),
             focused_background_color: default_focused_background_color(),
+            not_responding_color: default_not_responding_color(),
             outer_gap: default_outer_gap(),
             inner_gap: default_inner_gap(),
             smart_gaps: default_smart_gaps(),
+            snap_threshold: default_snap_threshold(),
+            close_timeout: default_close_timeout(),
+            ping_interval: default_ping_interval(),
             default_layout: default_layout(),
             workspaces: {
                 let mut workspaces: BTreeMap<u8, String> = BTreeMap::new();
@@ -1,38 +1,285 @@
 use super::config::Config;
+use super::validate::{self, Problem};
+use notify_rust::{Notification, Timeout};
+use ron::value::RawValue;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::{Map, Value};
+use crate::HadlockResult;
 use std::env;
+use std::fmt;
 use std::fs;
-use std::io::prelude::*;
 use std::path::*;
 
-pub(super) fn load_config() -> Config {
-    let args: Vec<String> = env::args().collect();
+/* Looked for in this order, the first one that exists is used */
+const FILE_NAMES: [&str; 3] = ["hadlock.toml", "hadlock.ron", "hadlock.json"];
 
-    let path = match args.len() {
-        2 => {
-            debug!("Path to config: {}", args.get(1).unwrap());
-            args.get(1).expect("Get config path")
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Json,
+    Toml,
+    Ron,
+}
+
+impl Format {
+    /* Told by the extension, anything unknown is read as JSON like before */
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Format::Toml,
+            Some("ron") => Format::Ron,
+            _ => Format::Json,
         }
+    }
+}
+
+/*
+ * hadlock.toml, hadlock.ron or hadlock.json in $XDG_CONFIG_HOME/hadlock,
+ * with ~/.config when XDG_CONFIG_HOME isn't set. hadlock.json when none exists
+ */
+pub fn default_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?
+        .join("hadlock");
+    FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .or_else(|| Some(dir.join("hadlock.json")))
+}
+
+/*
+ * Reads and validates the config at path. The config that comes back uses
+ * defaults for everything a problem was found in, parse errors tell the
+ * line and column
+ */
+pub fn read_config(path: &Path) -> (Config, Vec<Problem>) {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => return (Config::default(), vec![Problem::new("", e.to_string())]),
+    };
+    let (value, mut problems) = match parse(Format::of(path), &content) {
+        Ok(parsed) => parsed,
+        Err(e) => return (Config::default(), vec![Problem::new("", e)]),
+    };
+    let (config, invalid) = validate::validate(&value);
+    problems.extend(invalid);
+    (config, problems)
+}
+
+/* The config as a Value for validate, with the problems found while converting it */
+fn parse(format: Format, content: &str) -> Result<(Value, Vec<Problem>), String> {
+    match format {
+        Format::Json => serde_json::from_str(content)
+            .map(|value| (value, vec![]))
+            .map_err(|e| e.to_string()),
+        Format::Toml => toml::from_str(content)
+            .map(|value| (value, vec![]))
+            .map_err(|e| e.to_string()),
+        Format::Ron => parse_ron(content),
+    }
+}
 
-        x => {
-            println!(
-                "Wrong number of arguments:{}\nDefault config will be applied",
-                x
-            );
-            ""
+/*
+ * RON only names enum variants to a type that asks for them, so every key is
+ * read on its own as that key of a Config and turned into JSON. A key that
+ * can't be read is reported and left out, unknown keys are left to validate
+ */
+fn parse_ron(content: &str) -> Result<(Value, Vec<Problem>), String> {
+    let entries: RonEntries = ron::from_str(content).map_err(|e| e.to_string())?;
+    let known = serde_json::to_value(Config::default()).map_err(|e| e.to_string())?;
+    let mut object = Map::new();
+    let mut problems = vec![];
+    for (key, raw) in entries.0 {
+        if known.get(&key).is_none() {
+            object.insert(key, Value::Null);
+            continue;
+        }
+        match ron::from_str::<Config>(&format!("({}: {})", key, raw.get_ron())) {
+            Ok(config) => {
+                let value = serde_json::to_value(config).map_err(|e| e.to_string())?;
+                object.insert(key.clone(), value[&key].clone());
+            }
+            Err(e) => problems.push(Problem::new(key, e.code.to_string())),
+        }
+    }
+    Ok((Value::Object(object), problems))
+}
+
+/* Top level keys of a RON config with their RON text, a struct and a map are both taken */
+struct RonEntries(Vec<(String, Box<RawValue>)>);
+
+impl<'de> Deserialize<'de> for RonEntries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RonEntriesVisitor)
+    }
+}
+
+struct RonEntriesVisitor;
+
+impl<'de> Visitor<'de> for RonEntriesVisitor {
+    type Value = RonEntries;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a struct of config keys")
+    }
+
+    fn visit_unit<E>(self) -> Result<RonEntries, E> {
+        Ok(RonEntries(vec![]))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<RonEntries, A::Error> {
+        let mut entries = vec![];
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(RonEntries(entries))
+    }
+}
+
+/*
+ * Writes the JSON config at path as TOML next to it, with the same name
+ * and a .toml extension. The keys are carried over as they are, an existing
+ * TOML file is left alone
+ */
+pub fn convert_to_toml(path: &Path) -> HadlockResult<PathBuf> {
+    if Format::of(path) != Format::Json {
+        return Err(format!("{} is not a JSON config", path.display()).into());
+    }
+    let target = path.with_extension("toml");
+    if target.exists() {
+        return Err(format!("{} already exists", target.display()).into());
+    }
+    let value: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let toml = toml::to_string_pretty(&toml::Value::try_from(value)?)?;
+    fs::write(&target, toml)?;
+    Ok(target)
+}
+
+pub(super) fn load_config(path: Option<&Path>) -> Config {
+    let path = match path.map(PathBuf::from).or_else(default_path) {
+        Some(path) if path.is_file() => path,
+        path => {
+            debug!("No config file at {:?}, default config will be applied", path);
+            return Config::default();
         }
     };
+    debug!("Path to config: {}", path.display());
+    let (config, problems) = read_config(&path);
+    if !problems.is_empty() {
+        report(&path, &problems);
+    }
+    config
+}
+
+/* A broken config shouldn't leave a black screen, the problems are shown as a notification */
+fn report(path: &Path, problems: &[Problem]) {
+    problems
+        .iter()
+        .for_each(|problem| error!("{}: {}", path.display(), problem));
+    let body = problems
+        .iter()
+        .map(Problem::to_string)
+        .collect::<Vec<String>>()
+        .join("\n");
+    let res = Notification::new()
+        .summary(&format!("Problems in {}, defaults are used for them", path.display()))
+        .body(&body)
+        .timeout(Timeout::Milliseconds(15000))
+        .show();
+    if let Err(e) = res {
+        warn!("Failed to show config problems: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_errors_have_a_position() {
+        let path = env::temp_dir().join(format!("hadlock-config-{}.json", std::process::id()));
+        fs::write(&path, "{\n    \"borderWidth\": 2,\n}").expect("config");
+        let (config, problems) = read_config(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(config.border_width, Config::default().border_width);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].to_string().contains("line 3 column 1"), "{}", problems[0]);
+    }
+
+    #[test]
+    fn every_format_reads_the_same_config() {
+        let dir = env::temp_dir().join(format!("hadlock-formats-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("config dir");
+        let json = dir.join("hadlock.json");
+        fs::write(
+            &json,
+            r##"{
+                "borderWidth": 4,
+                "borderColor": { "Custom": "#480222" },
+                "defaultLayout": "ColumnMaster",
+                "workspaces": { "1": "web", "2": "mail" },
+                "commands": [{ "execTime": "Post", "program": "sh", "args": ["-c", "true"] }]
+            }"##,
+        )
+        .expect("json config");
+        let ron = dir.join("hadlock.ron");
+        fs::write(
+            &ron,
+            r##"(
+                borderWidth: 4,
+                borderColor: Custom("#480222"),
+                defaultLayout: ColumnMaster,
+                workspaces: { 1: "web", 2: "mail" },
+                commands: [(execTime: Post, program: "sh", args: ["-c", "true"])],
+            )"##,
+        )
+        .expect("ron config");
+        let toml = convert_to_toml(&json).expect("converted");
+        assert!(convert_to_toml(&json).is_err(), "the TOML config is overwritten");
+
+        let configs: Vec<String> = [&json, &toml, &ron]
+            .iter()
+            .map(|path| {
+                let (config, problems) = read_config(path);
+                assert_eq!(problems, vec![], "{}", path.display());
+                serde_json::to_string(&config).expect("serialized")
+            })
+            .collect();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(configs[0], configs[1]);
+        assert_eq!(configs[0], configs[2]);
+        assert!(configs[0].contains("\"borderColor\":{\"Custom\":\"#480222\"}"), "{}", configs[0]);
+    }
+
+    #[test]
+    fn ron_problems_are_reported_per_key() {
+        let path = env::temp_dir().join(format!("hadlock-config-{}.ron", std::process::id()));
+        fs::write(
+            &path,
+            r##"(
+                borderwidth: 4,
+                innerGap: 7,
+                outerGap: "wide",
+                borderColor: Custom("#48022"),
+                defaultLayout: ColumnMaster,
+                workspaces: { 1: "web", 10: "mail" },
+            )"##,
+        )
+        .expect("ron config");
+        let (config, problems) = read_config(&path);
+        let _ = fs::remove_file(&path);
+        let problems: Vec<String> = problems.iter().map(Problem::to_string).collect();
+
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems[0].starts_with("outerGap: "), "{}", problems[0]);
+        assert!(problems[1].starts_with("borderColor: "), "{}", problems[1]);
+        assert_eq!(problems[2], "borderwidth: unknown key, did you mean borderWidth?");
+        assert_eq!(problems[3], "workspaces.10: workspace index out of range, use 1 to 9");
 
-    let path = Path::new(path);
-
-    if path.exists() && path.is_file() {
-        let mut file = fs::File::open(path).expect(&format!("Failed to open file: {:?}", path));
-        let mut file_content = String::new();
-        file.read_to_string(&mut file_content)
-            .expect(&format!("Failed to read file content: {:?}", path));
-        let config: Config = serde_json::from_str(&file_content).expect("Failed to map config");
-        Config::from(config)
-    } else {
-        debug!("Path either doesn't exist or is not a file");
-        Config::default()
+        // The valid keys still apply
+        assert_eq!(config.inner_gap, 7);
+        assert_eq!(config.default_layout, crate::layout::LayoutTag::ColumnMaster);
+        assert_eq!(config.outer_gap, Config::default().outer_gap);
     }
 }
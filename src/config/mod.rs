@@ -1,15 +1,24 @@
 pub mod config;
 mod loader;
+mod validate;
 
 use lazy_static::*;
 use serde::{Deserialize, Serialize};
-
-
+use std::path::PathBuf;
+use std::sync::OnceLock;
 
 use config::*;
+pub use loader::{convert_to_toml, default_path, read_config};
 
 lazy_static! {
-    pub static ref CONFIG: Config = { loader::load_config() };
+    pub static ref CONFIG: Config = loader::load_config(CONFIG_PATH.get().map(PathBuf::as_path));
+}
+
+/* Config file given on the command line, it has to be set before CONFIG is used */
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_config_path(path: PathBuf) {
+    let _ = CONFIG_PATH.set(path);
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -19,6 +28,7 @@ pub enum ExecTime {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Command {
     #[serde(rename = "execTime")]
     pub exec_time: ExecTime,
@@ -0,0 +1,267 @@
+use super::{config::Config, Command, ExecTime};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{env, fmt, ops::RangeInclusive, path::Path};
+
+/* Workspaces are switched to with mod + 1 to 9 */
+const WORKSPACES: RangeInclusive<u8> = 1..=9;
+
+const COLOR_KEYS: [&str; 4] = [
+    "borderColor",
+    "backgroundColor",
+    "focusedBackgroundColor",
+    "notRespondingColor",
+];
+
+/* Something wrong with a config, path is the dotted path of the key it is about */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub path: String,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/*
+ * Checks every key of config and reports all problems instead of the first
+ * one. Keys with a problem are left out, so their defaults apply and the
+ * rest of the config is still used
+ */
+pub fn validate(config: &Value) -> (Config, Vec<Problem>) {
+    let mut problems = vec![];
+    let object = match config.as_object() {
+        Some(object) => object,
+        None => {
+            problems.push(Problem::new("", "the config has to be an object"));
+            return (Config::default(), problems);
+        }
+    };
+    let known = keys_of(&Config::default());
+
+    let mut valid = Map::new();
+    for (key, value) in object {
+        if !known.contains(key) {
+            problems.push(unknown_key(key, key, &known));
+            continue;
+        }
+        let found = problems.len();
+        match key.as_str() {
+            "workspaces" => check_workspaces(value, &mut problems),
+            "commands" => check_commands(value, &mut problems),
+            key if COLOR_KEYS.contains(&key) => check_color(key, value, &mut problems),
+            _ => (),
+        }
+        if problems.len() > found {
+            continue;
+        }
+        let mut single = Map::new();
+        single.insert(key.clone(), value.clone());
+        match serde_json::from_value::<Config>(Value::Object(single)) {
+            Ok(_) => {
+                valid.insert(key.clone(), value.clone());
+            }
+            Err(e) => problems.push(Problem::new(key.as_str(), e.to_string())),
+        }
+    }
+
+    let config: Config = serde_json::from_value(Value::Object(valid)).unwrap_or_default();
+    // Commands that can't be found are only reported, starting them fails harmlessly
+    for (i, cmd) in config.commands.iter().enumerate() {
+        if !on_path(&cmd.program) {
+            problems.push(Problem::new(
+                format!("commands.{}.program", i),
+                format!("{} not found on PATH", cmd.program),
+            ));
+        }
+    }
+    (config, problems)
+}
+
+fn check_workspaces(value: &Value, problems: &mut Vec<Problem>) {
+    let workspaces = match value.as_object() {
+        Some(workspaces) => workspaces,
+        None => return,
+    };
+    for (index, name) in workspaces {
+        let path = format!("workspaces.{}", index);
+        match index.parse::<u8>() {
+            Ok(index) if WORKSPACES.contains(&index) => (),
+            _ => problems.push(Problem::new(
+                path.as_str(),
+                format!(
+                    "workspace index out of range, use {} to {}",
+                    WORKSPACES.start(),
+                    WORKSPACES.end()
+                ),
+            )),
+        }
+        if !name.is_string() {
+            problems.push(Problem::new(path, "the workspace name has to be a string"));
+        }
+    }
+}
+
+fn check_commands(value: &Value, problems: &mut Vec<Problem>) {
+    let commands = match value.as_array() {
+        Some(commands) => commands,
+        None => return,
+    };
+    let known = keys_of(&Command {
+        exec_time: ExecTime::Pre,
+        program: String::new(),
+        args: vec![],
+    });
+    for (i, command) in commands.iter().enumerate() {
+        let path = format!("commands.{}", i);
+        let found = problems.len();
+        if let Some(object) = command.as_object() {
+            for key in object.keys().filter(|key| !known.contains(key)) {
+                problems.push(unknown_key(&format!("{}.{}", path, key), key, &known));
+            }
+        }
+        if problems.len() > found {
+            continue;
+        }
+        if let Err(e) = serde_json::from_value::<Command>(command.clone()) {
+            problems.push(Problem::new(path, e.to_string()));
+        }
+    }
+}
+
+fn check_color(key: &str, value: &Value, problems: &mut Vec<Problem>) {
+    let custom = match value.get("Custom") {
+        Some(custom) => custom,
+        None => return,
+    };
+    let path = format!("{}.Custom", key);
+    match custom.as_str() {
+        Some(hex) => {
+            let digits = hex.trim_start_matches('#');
+            if digits.len() != 6 || u64::from_str_radix(digits, 16).is_err() {
+                problems.push(Problem::new(
+                    path,
+                    format!("invalid hex {:?}, expected #rrggbb", hex),
+                ));
+            }
+        }
+        None => problems.push(Problem::new(path, "expected a hex string like #rrggbb")),
+    }
+}
+
+/* The keys value serializes to, these are the keys its config section accepts */
+fn keys_of<T: Serialize>(value: &T) -> Vec<String> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(object)) => object.keys().cloned().collect(),
+        _ => vec![],
+    }
+}
+
+fn unknown_key(path: &str, key: &str, known: &[String]) -> Problem {
+    let similar = known
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(key))
+        .or_else(|| {
+            known
+                .iter()
+                .min_by_key(|known| distance(known, key))
+                .filter(|known| distance(known, key) <= 2)
+        });
+    match similar {
+        Some(similar) => Problem::new(path, format!("unknown key, did you mean {}?", similar)),
+        None => Problem::new(
+            path,
+            format!("unknown key, expected one of {}", known.join(", ")),
+        ),
+    }
+}
+
+/* Levenshtein distance, small enough for config keys */
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { prev } else { prev + 1 };
+            prev = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+        }
+    }
+    row[b.len()]
+}
+
+fn on_path(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn every_problem_is_reported_with_its_path() {
+        let (config, problems) = validate(&json!({
+            "borderwidth": 4,
+            "innerGap": 7,
+            "outerGap": "wide",
+            "borderColor": { "Custom": "#48022" },
+            "workspaces": { "1": "web", "10": "mail" },
+            "commands": [
+                { "execTime": "Pre", "program": "sh", "args": [], "wait": true },
+                { "execTime": "Post", "program": "no-such-program-hadlock", "args": [] }
+            ]
+        }));
+        let problems: Vec<String> = problems.iter().map(Problem::to_string).collect();
+
+        assert_eq!(problems[0], "borderColor.Custom: invalid hex \"#48022\", expected #rrggbb");
+        assert_eq!(problems[1], "borderwidth: unknown key, did you mean borderWidth?");
+        assert_eq!(problems[2], "commands.0.wait: unknown key, expected one of args, execTime, program");
+        assert!(problems[3].starts_with("outerGap: invalid type"), "{}", problems[3]);
+        assert_eq!(problems[4], "workspaces.10: workspace index out of range, use 1 to 9");
+        assert_eq!(problems.len(), 5);
+
+        // The valid keys still apply
+        assert_eq!(config.inner_gap, 7);
+        assert_eq!(config.outer_gap, Config::default().outer_gap);
+        assert_eq!(config.workspaces.len(), 9);
+    }
+
+    #[test]
+    fn missing_programs_are_reported() {
+        let (config, problems) = validate(&json!({
+            "commands": [{ "execTime": "Post", "program": "no-such-program-hadlock", "args": [] }]
+        }));
+        assert_eq!(config.commands.len(), 1);
+        assert_eq!(
+            problems,
+            vec![Problem::new(
+                "commands.0.program",
+                "no-such-program-hadlock not found on PATH"
+            )]
+        );
+    }
+}
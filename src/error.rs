@@ -0,0 +1,130 @@
+use {
+    crate::xlibwrapper::xlibmodels::{MonitorId, Window},
+    std::{error::Error, fmt},
+    x11_dl::xlib,
+};
+
+/*
+ * What can go wrong while reducing an action. None of it is fatal, the
+ * reducer gives up on the action and the error is logged
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum HadlockError {
+    NoMonitor(MonitorId),
+    NoWorkspace(u32),
+    NoClient(Window),
+    /* A window or point that isn't inside any screen */
+    OffScreen,
+    /* A client message without the five data items of format 32 */
+    MalformedMessage(u64),
+    /* An error the X server sent back for a request */
+    X {
+        error_code: u8,
+        request_code: u8,
+        resource: u64,
+    },
+}
+
+impl HadlockError {
+    /*
+     * Windows can be gone before their DestroyNotify is handled, errors about
+     * them are expected when apps close quickly
+     */
+    pub fn is_window_race(&self) -> bool {
+        match self {
+            Self::NoClient(_) => true,
+            Self::X { error_code, .. } => *error_code == xlib::BadWindow,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for HadlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoMonitor(id) => write!(f, "no monitor {}", id),
+            Self::NoWorkspace(ws) => write!(f, "no workspace {}", ws),
+            Self::NoClient(w) => write!(f, "window {:#x} isn't managed", w),
+            Self::OffScreen => write!(f, "not inside any screen"),
+            Self::MalformedMessage(message_type) => {
+                write!(f, "malformed client message of type {}", message_type)
+            }
+            Self::X {
+                error_code,
+                request_code,
+                resource,
+            } => write!(
+                f,
+                "X error {} for request {} on resource {:#x}",
+                error_name(*error_code),
+                request_code,
+                resource
+            ),
+        }
+    }
+}
+
+impl Error for HadlockError {}
+
+impl From<&xlib::XErrorEvent> for HadlockError {
+    fn from(e: &xlib::XErrorEvent) -> Self {
+        Self::X {
+            error_code: e.error_code,
+            request_code: e.request_code,
+            resource: e.resourceid,
+        }
+    }
+}
+
+/* Logs what went wrong in the reducer for action, window races are only warned about */
+pub fn report(action: &str, res: Result<(), HadlockError>) {
+    match res {
+        Err(e) if e.is_window_race() => warn!("{}: {}", action, e),
+        Err(e) => error!("{}: {}", action, e),
+        Ok(()) => (),
+    }
+}
+
+fn error_name(error_code: u8) -> &'static str {
+    match error_code {
+        xlib::BadRequest => "BadRequest",
+        xlib::BadValue => "BadValue",
+        xlib::BadWindow => "BadWindow",
+        xlib::BadPixmap => "BadPixmap",
+        xlib::BadAtom => "BadAtom",
+        xlib::BadCursor => "BadCursor",
+        xlib::BadFont => "BadFont",
+        xlib::BadMatch => "BadMatch",
+        xlib::BadDrawable => "BadDrawable",
+        xlib::BadAccess => "BadAccess",
+        xlib::BadAlloc => "BadAlloc",
+        xlib::BadColor => "BadColor",
+        xlib::BadGC => "BadGC",
+        xlib::BadIDChoice => "BadIDChoice",
+        xlib::BadName => "BadName",
+        xlib::BadLength => "BadLength",
+        xlib::BadImplementation => "BadImplementation",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bad_window_is_a_race() {
+        let bad_window = HadlockError::X {
+            error_code: xlib::BadWindow,
+            request_code: 12,
+            resource: 0x40_0001,
+        };
+        assert!(bad_window.is_window_race());
+        assert_eq!(
+            bad_window.to_string(),
+            "X error BadWindow for request 12 on resource 0x400001"
+        );
+        assert!(HadlockError::NoClient(3).is_window_race());
+        assert!(!HadlockError::NoMonitor(1).is_window_race());
+    }
+}
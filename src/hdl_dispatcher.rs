@@ -1,176 +1,364 @@
 use {
+    crate::config::CONFIG,
     crate::hdl_reactor::HdlReactor,
-    crate::models::internal_action,
+    crate::models::{internal_action, window_type::WindowType},
+    crate::recording::{Dispatched, Recorder},
+    crate::session::Session,
     crate::state::State,
     crate::xlibwrapper::core::XlibWrapper,
     crate::xlibwrapper::{action, xlibmodels::*},
     reducer::*,
+    nix::{
+        errno::Errno,
+        poll::{poll, PollFd, PollFlags},
+        sys::{
+            signal::{SigSet, Signal},
+            signalfd::{SfdFlags, SignalFd},
+        },
+    },
+    std::env,
+    std::os::unix::{io::AsRawFd, process::CommandExt},
+    std::process::Command,
     std::rc::Rc,
-    std::sync::mpsc::*,
+    std::sync::mpsc::Sender,
+    std::time::Duration,
     x11_dl::xlib,
 };
 
-pub fn run(xlib: Rc<XlibWrapper>, sender: Sender<bool>) {
-    let (tx, rx) = channel::<internal_action::InternalAction>();
+pub fn run(xlib: Rc<XlibWrapper>, sender: Sender<bool>, session: Option<Session>) {
+    let (tx, rx) = internal_action::channel().expect("hdl_dispatcher: internal action channel");
+    // Clients and a restarted hadlock don't get to keep it
+    let mut signals = SignalFd::with_flags(
+        &shutdown_signals(),
+        SfdFlags::SFD_NONBLOCK | SfdFlags::SFD_CLOEXEC,
+    )
+    .expect("hdl_dispatcher: signalfd");
+    if CONFIG.ping_interval > 0 {
+        tx.send_after(
+            Duration::from_millis(CONFIG.ping_interval),
+            internal_action::InternalAction::PingFocused,
+        );
+    }
     let state = State::new(xlib.clone(), tx.clone());
-    let mut store = Store::new(state, HdlReactor::new(xlib.clone(), tx));
+    let mut store = Store::new(state, HdlReactor::new(xlib.clone(), tx.clone()));
+    let mut recorder = Recorder::from_env(xlib.get_screens());
 
     //setup
     xlib.grab_server();
-    let _ = xlib.get_top_level_windows().iter().map(|w| {
-        store.dispatch(action::MapRequest {
-            win: *w,
-            parent: xlib.get_root(),
-        });
-    });
+    adopt_windows(&xlib, &mut store, &mut recorder, session);
     xlib.ungrab_server();
     let _ = sender.send(true);
 
     loop {
-        let xevent = xlib.next_event();
-        //debug!("Event: {:?}", xevent);
-        match xevent.get_type() {
-            xlib::ConfigureRequest => {
-                let event = xlib::XConfigureRequestEvent::from(xevent);
-                let window_changes = WindowChanges {
-                    x: event.x,
-                    y: event.y,
-                    width: event.width,
-                    height: event.height,
-                    border_width: event.border_width,
-                    sibling: event.above,
-                    stack_mode: event.detail,
-                };
-                store.dispatch(action::ConfigurationRequest {
-                    win: event.window,
-                    win_changes: window_changes,
-                    value_mask: event.value_mask,
-                    parent: event.parent,
-                })
+        while xlib.pending() > 0 {
+            let xevent = xlib.next_event();
+            if xlib.lost_wm_selection(&xevent) {
+                info!("Another window manager is taking over, shutting down");
+                xlib.exit();
+                return;
             }
-            xlib::MapRequest => {
-                let event = xlib::XMapRequestEvent::from(xevent);
-                /*debug!(
-                    "window type: {}",
-                    xlib.get_window_type(event.window).get_name()
-                );*/
-                store.dispatch(action::MapRequest {
-                    win: event.window,
-                    parent: event.parent,
-                })
+            if let Some(action) = translate_event(xevent) {
+                dispatch(&mut store, &mut recorder, action);
             }
-            xlib::UnmapNotify => {
-                let event = xlib::XUnmapEvent::from(xevent);
-                store.dispatch(action::UnmapNotify { win: event.window })
-            }
-            xlib::ButtonPress => {
-                let event = xlib::XButtonEvent::from(xevent);
-                store.dispatch(action::ButtonPress {
-                    win: event.window,
-                    sub_win: event.subwindow,
-                    button: event.button,
-                    x_root: event.x_root as u32,
-                    y_root: event.y_root as u32,
-                    state: event.state as u32,
-                });
+        }
+        // Actions queued while handling these wait for the next round
+        for action in rx.drain() {
+            if let internal_action::InternalAction::Restart = action {
+                match Session::from_state(&store).save() {
+                    Ok(path) => info!("Restarting, session saved to {:?}", path),
+                    Err(e) => {
+                        error!("Not restarting, failed to save the session: {}", e);
+                        continue;
+                    }
+                }
+                restart(&xlib);
+                return;
             }
-            xlib::ButtonRelease => {
-                let event = xlib::XButtonEvent::from(xevent);
-                store.dispatch(action::ButtonRelease {
-                    win: event.window,
-                    sub_win: event.subwindow,
-                    button: event.button,
-                    x_root: event.x_root as u32,
-                    y_root: event.y_root as u32,
-                    state: event.state as u32,
-                })
+            if let Some(action) = translate_internal_action(&xlib, &tx, action) {
+                dispatch(&mut store, &mut recorder, action);
             }
-            xlib::KeyPress => {
-                let event = xlib::XKeyEvent::from(xevent);
-                store.dispatch(action::KeyPress {
-                    win: event.window,
-                    state: event.state,
-                    keycode: event.keycode,
-                })
+        }
+        if xlib.pending() > 0 {
+            continue;
+        }
+
+        let timeout = rx
+            .next_timeout()
+            .map(|timeout| timeout.as_millis().min(i32::MAX as u128) as i32)
+            .unwrap_or(-1);
+        let mut fds = [
+            PollFd::new(xlib.connection_fd(), PollFlags::POLLIN),
+            PollFd::new(rx.wake_fd(), PollFlags::POLLIN),
+            PollFd::new(signals.as_raw_fd(), PollFlags::POLLIN),
+        ];
+        if let Err(e) = poll(&mut fds, timeout) {
+            if e != nix::Error::Sys(Errno::EINTR) {
+                error!("hdl_dispatcher: poll failed: {}", e);
+                return;
             }
-            /*xlib::KeyRelease => {
+        }
+        if let Ok(Some(signal)) = signals.read_signal() {
+            info!("Shutting down on signal {}", signal.ssi_signo);
+            return;
+        }
+    }
+}
+
+/*
+ * Signals that end the event loop, they are blocked before any thread is
+ * spawned and read from a signalfd instead
+ */
+pub fn shutdown_signals() -> SigSet {
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGINT);
+    signals.add(Signal::SIGTERM);
+    signals
+}
+
+/*
+ * Manages the windows that are already there. The ones a restart saved go
+ * back to their workspaces, every other viewable one is handled as if it had
+ * just asked to be mapped. Docks come first so clients are placed inside
+ * their struts, and a _NET_WM_DESKTOP left by the previous window manager
+ * moves the client the way a pager asking for it would
+ */
+fn adopt_windows(
+    xlib: &XlibWrapper,
+    store: &mut Store<State<XlibWrapper>, HdlReactor<XlibWrapper>>,
+    recorder: &mut Option<Recorder>,
+    session: Option<Session>,
+) {
+    let mut windows = xlib.get_top_level_windows();
+    let mut restored = vec![];
+    if let Some(mut session) = session {
+        session.retain(&windows);
+        restored = session.clients();
+        let action = Dispatched::RestoreSession(action::RestoreSession { session });
+        dispatch(store, recorder, action);
+    }
+    windows.retain(|w| {
+        let attrs = xlib.get_window_attributes(*w);
+        !restored.contains(w) && !attrs.override_redirect && attrs.map_state == xlib::IsViewable
+    });
+    windows.sort_by_key(|w| xlib.get_window_type(*w) != WindowType::Dock);
+
+    for w in windows {
+        // Mapping the window sets _NET_WM_DESKTOP, read the old one first
+        let desktop = xlib.get_net_wm_desktop(w);
+        let action = Dispatched::MapRequest(action::MapRequest {
+            win: w,
+            parent: xlib.get_root(),
+        });
+        dispatch(store, recorder, action);
+        if let Some(desktop) = desktop {
+            let action = Dispatched::ClientMessageRequest(action::ClientMessageRequest {
+                win: w,
+                message_type: xlib.xatom.NetWMDesktop,
+                data: vec![i64::from(desktop), 0, 0, 0, 0],
+            });
+            dispatch(store, recorder, action);
+        }
+    }
+}
+
+/*
+ * Replaces hadlock with the binary it was started as, which picks the saved
+ * session up. Closing the display first hands every window in the save-set
+ * back mapped, so they stay around when exec fails. The session is removed
+ * then, nothing is left to restore it
+ */
+fn restart(xlib: &XlibWrapper) {
+    let mut args = env::args_os();
+    let program = match args.next() {
+        Some(program) => program,
+        None => {
+            error!("Can't restart, hadlock was started without argv[0]");
+            Session::discard();
+            return;
+        }
+    };
+    xlib.exit();
+    let e = Command::new(&program).args(args).exec();
+    error!("Failed to restart {:?}: {}", program, e);
+    Session::discard();
+}
+
+/* The action an X event is dispatched as, None for the ones hadlock ignores */
+fn translate_event(xevent: xlib::XEvent) -> Option<Dispatched> {
+    //debug!("Event: {:?}", xevent);
+    let action = match xevent.get_type() {
+        xlib::ConfigureRequest => {
+            let event = xlib::XConfigureRequestEvent::from(xevent);
+            let window_changes = WindowChanges {
+                x: event.x,
+                y: event.y,
+                width: event.width,
+                height: event.height,
+                border_width: event.border_width,
+                sibling: event.above,
+                stack_mode: event.detail,
+            };
+            Dispatched::ConfigurationRequest(action::ConfigurationRequest {
+                win: event.window,
+                win_changes: window_changes,
+                value_mask: event.value_mask,
+                parent: event.parent,
+            })
+        }
+        xlib::MapRequest => {
+            let event = xlib::XMapRequestEvent::from(xevent);
+            /*debug!(
+                "window type: {}",
+                xlib.get_window_type(event.window).get_name()
+            );*/
+            Dispatched::MapRequest(action::MapRequest {
+                win: event.window,
+                parent: event.parent,
+            })
+        }
+        xlib::UnmapNotify => {
+            let event = xlib::XUnmapEvent::from(xevent);
+            Dispatched::UnmapNotify(action::UnmapNotify { win: event.window })
+        }
+        xlib::ButtonPress => {
+            let event = xlib::XButtonEvent::from(xevent);
+            Dispatched::ButtonPress(action::ButtonPress {
+                win: event.window,
+                sub_win: event.subwindow,
+                button: event.button,
+                x_root: event.x_root as u32,
+                y_root: event.y_root as u32,
+                state: event.state as u32,
+            })
+        }
+        xlib::ButtonRelease => {
+            let event = xlib::XButtonEvent::from(xevent);
+            Dispatched::ButtonRelease(action::ButtonRelease {
+                win: event.window,
+                sub_win: event.subwindow,
+                button: event.button,
+                x_root: event.x_root as u32,
+                y_root: event.y_root as u32,
+                state: event.state as u32,
+            })
+        }
+        xlib::KeyPress => {
             let event = xlib::XKeyEvent::from(xevent);
-            action::KeyRelease{win: event.window, state: event.state, keycode: event.keycode};
-            },*/
-            xlib::MotionNotify => {
-                //debug!("motion");
+            Dispatched::KeyPress(action::KeyPress {
+                win: event.window,
+                state: event.state,
+                keycode: event.keycode,
+            })
+        }
+        /*xlib::KeyRelease => {
+        let event = xlib::XKeyEvent::from(xevent);
+        action::KeyRelease{win: event.window, state: event.state, keycode: event.keycode};
+        },*/
+        xlib::MotionNotify => {
+            //debug!("motion");
 
-                let event = xlib::XMotionEvent::from(xevent);
-                store.dispatch(action::MotionNotify {
-                    win: event.window,
-                    sub_win: event.subwindow,
-                    x_root: event.x_root,
-                    y_root: event.y_root,
-                    state: event.state,
-                })
-            }
-            xlib::EnterNotify => {
-                let event = xlib::XCrossingEvent::from(xevent);
-                store.dispatch(action::EnterNotify {
-                    win: event.window,
-                    sub_win: event.subwindow,
-                })
-            }
-            xlib::LeaveNotify => {
-                let event = xlib::XCrossingEvent::from(xevent);
-                store.dispatch(action::LeaveNotify { win: event.window })
-            }
-            /*xlib::Expose => {
-            let event = xlib::XExposeEvent::from(xevent);
-            action::Expose{win: event.window};
-            },*/
-            /*xlib::DestroyNotify => {
-                let event = xlib::XDestroyWindowEvent::from(xevent);
-                store.dispatch(action::DestroyNotify { win: event.window })
-            }*/
-            xlib::PropertyNotify => {
-                let event = xlib::XPropertyEvent::from(xevent);
-                action::PropertyNotify {
-                    win: event.window,
-                    atom: event.atom,
-                };
-            }
-            xlib::ClientMessage => {
-                let event = xlib::XClientMessageEvent::from(xevent);
-                //debug!("ClientMessage: {:#?}", event);
-                store.dispatch(action::ClientMessageRequest {
-                    win: event.window,
-                    message_type: event.message_type,
-                    data: vec![
-                        event.data.get_long(0),
-                        event.data.get_long(1),
-                        event.data.get_long(2),
-                    ],
-                });
-            }
-            _ => store.dispatch(action::UnknownEvent),
+            let event = xlib::XMotionEvent::from(xevent);
+            Dispatched::MotionNotify(action::MotionNotify {
+                win: event.window,
+                sub_win: event.subwindow,
+                x_root: event.x_root,
+                y_root: event.y_root,
+                state: event.state,
+            })
+        }
+        xlib::EnterNotify => {
+            let event = xlib::XCrossingEvent::from(xevent);
+            Dispatched::EnterNotify(action::EnterNotify {
+                win: event.window,
+                sub_win: event.subwindow,
+            })
+        }
+        xlib::LeaveNotify => {
+            let event = xlib::XCrossingEvent::from(xevent);
+            Dispatched::LeaveNotify(action::LeaveNotify { win: event.window })
+        }
+        /*xlib::Expose => {
+        let event = xlib::XExposeEvent::from(xevent);
+        action::Expose{win: event.window};
+        },*/
+        xlib::DestroyNotify => {
+            let event = xlib::XDestroyWindowEvent::from(xevent);
+            Dispatched::DestroyNotify(action::DestroyNotify { win: event.window })
         }
+        // Not dispatched, the property_notify reducer isn't wired up yet
+        xlib::PropertyNotify => return None,
+        xlib::ClientMessage => {
+            let event = xlib::XClientMessageEvent::from(xevent);
+            //debug!("ClientMessage: {:#?}", event);
+            Dispatched::ClientMessageRequest(action::ClientMessageRequest {
+                win: event.window,
+                message_type: event.message_type,
+                data: vec![
+                    event.data.get_long(0),
+                    event.data.get_long(1),
+                    event.data.get_long(2),
+                    event.data.get_long(3),
+                    event.data.get_long(4),
+                ],
+            })
+        }
+        _ => Dispatched::UnknownEvent(action::UnknownEvent),
+    };
+    Some(action)
+}
 
-        match rx.try_recv() {
-            Ok(action) => match action {
-                internal_action::InternalAction::Focus => {
-                    //debug!("Motion dispatch focus");
-                    if let Some(win) = xlib.window_under_pointer() {
-                        store.dispatch(action::Focus { win})
-                    }
-                },
-                internal_action::InternalAction::FocusSpecific(win) => {
-                    store.dispatch(action::Focus{ win })
-                }
-                internal_action::InternalAction::UpdateLayout => {
-                    debug!("UpdateLayout");
-                    store.dispatch(action::UpdateLayout)
-                },
-                internal_action::InternalAction::Destroy(win) => {
-                    store.dispatch(action::Destroy { win })
-                },
-            },
-            Err(_) => (),
+/* The action an internal action is dispatched as, lookups that need X happen here */
+fn translate_internal_action(
+    xlib: &XlibWrapper,
+    tx: &internal_action::ActionSender,
+    action: internal_action::InternalAction,
+) -> Option<Dispatched> {
+    let action = match action {
+        internal_action::InternalAction::Focus => {
+            //debug!("Motion dispatch focus");
+            Dispatched::Focus(action::Focus {
+                win: xlib.window_under_pointer()?,
+            })
+        },
+        internal_action::InternalAction::FocusSpecific(win) => {
+            Dispatched::Focus(action::Focus{ win })
         }
+        internal_action::InternalAction::UpdateLayout => {
+            debug!("UpdateLayout");
+            Dispatched::UpdateLayout(action::UpdateLayout)
+        },
+        internal_action::InternalAction::Destroy(win) => {
+            Dispatched::Destroy(action::Destroy { win })
+        },
+        internal_action::InternalAction::Kill(win, serial) => {
+            Dispatched::Kill(action::Kill { win, serial })
+        },
+        internal_action::InternalAction::PingFocused => {
+            tx.send_after(
+                Duration::from_millis(CONFIG.ping_interval),
+                internal_action::InternalAction::PingFocused,
+            );
+            Dispatched::PingFocused(action::PingFocused)
+        },
+        internal_action::InternalAction::PingTimeout(win, serial) => {
+            Dispatched::PingTimeout(action::PingTimeout { win, serial })
+        },
+        internal_action::InternalAction::Restore(win) => {
+            Dispatched::Restore(action::Restore { win })
+        },
+        // The event loop restarts before actions are translated
+        internal_action::InternalAction::Restart => return None,
+    };
+    Some(action)
+}
+
+fn dispatch(
+    store: &mut Store<State<XlibWrapper>, HdlReactor<XlibWrapper>>,
+    recorder: &mut Option<Recorder>,
+    action: Dispatched,
+) {
+    if let Some(recorder) = recorder {
+        recorder.record(&action);
     }
+    action.dispatch(store);
 }
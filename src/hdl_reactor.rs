@@ -1,27 +1,45 @@
 use {
     crate::config::CONFIG,
-    crate::models::{internal_action::InternalAction, windowwrapper::*, HandleState, WindowState},
+    crate::models::{
+        internal_action::{ActionSender, InternalAction},
+        windowwrapper::*, HandleState, Layer, SnapZone, WindowState,
+    },
     crate::state::*,
+    crate::wm,
     crate::{
-        xlibwrapper::core::XlibWrapper,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::xlibmodels::*,
         xlibwrapper::{masks::*, util::*},
     },
     reducer::*,
-    std::rc::Rc,
-    std::sync::mpsc::Sender,
+    std::{cell::RefCell, collections::HashMap, rc::Rc},
 };
 
-pub struct HdlReactor {
-    lib: Rc<XlibWrapper>,
-    tx: Sender<InternalAction>,
+pub struct HdlReactor<W: WindowSystem> {
+    lib: Rc<W>,
+    tx: ActionSender,
+    properties: RefCell<HashMap<Window, ClientProperties>>,
+}
+
+/* What the properties of a client were last written from, they are only written again when it changes */
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ClientProperties {
+    desktop: u32,
+    visible: bool,
+    minimized: bool,
+    state: WindowState,
+    snap_zone: Option<SnapZone>,
+    layer: Layer,
+    sticky: bool,
+    decorated: bool,
 }
 
-impl Reactor<State> for HdlReactor {
+impl<W: WindowSystem> Reactor<State<W>> for HdlReactor<W> {
     type Output = ();
 
-    fn react(&self, state: &State) {
+    fn react(&self, state: &State<W>) {
         //debug!("{:#?}", state);
+        let mut restacked = false;
 
         state.monitors.values().for_each(|mon| {
             let handle_state = *mon.handle_state.borrow();
@@ -29,6 +47,7 @@ impl Reactor<State> for HdlReactor {
                 HandleState::Focus => {
                     debug!("Setting current monitor to: {}", state.current_monitor);
                     self.lib.update_desktops(mon.current_ws, None);
+                    wm::update_work_areas(state);
                     mon.handle_state.replace(HandleState::Handled);
                 }
                 HandleState::UpdateLayout => {
@@ -48,6 +67,8 @@ impl Reactor<State> for HdlReactor {
                         .iter()
                         .for_each(|handle_state| match handle_state {
                             HandleState::New => {
+                                // Window ids are reused, what was written for an old window doesn't count
+                                self.properties.borrow_mut().remove(key);
                                 self.lib.add_to_save_set(*key);
                                 self.lib.add_to_root_net_client_list(*key);
                                 self.lib.set_border_width(*key, CONFIG.border_width as u32);
@@ -56,6 +77,7 @@ impl Reactor<State> for HdlReactor {
                                 self.lib.resize_window(*key, val.get_size());
                                 self.subscribe_to_events(*key);
                                 self.lib.map_window(*key);
+                                wm::push_stack(state, *key);
                                 set_handled = true;
                             }
                             HandleState::Map => {
@@ -65,7 +87,7 @@ impl Reactor<State> for HdlReactor {
                                 set_handled = true;
                             }
                             HandleState::Unmap => {
-                                self.lib.unmap_window(*key);
+                                wm::unmap_window(state, *key);
                                 set_handled = true;
                             }
                             HandleState::Move => {
@@ -75,7 +97,7 @@ impl Reactor<State> for HdlReactor {
                             HandleState::Center => {
                                 self.lib.move_window(*key, val.get_position());
                                 self.lib.resize_window(*key, val.get_size());
-                                self.lib.raise_window(*key);
+                                wm::push_stack(state, *key);
                                 self.set_focus(*key, val);
                                 set_handled = true;
                             }
@@ -105,7 +127,7 @@ impl Reactor<State> for HdlReactor {
                                 self.lib.resize_window(*key, val.get_size());
                                 self.set_focus(*key, &val);
                                 self.lib.set_border_width(*key, 0);
-                                self.lib.raise_window(*key);
+                                wm::push_stack(state, *key);
                                 self.lib.center_cursor(*key);
                                 set_handled = true;
                             }
@@ -116,13 +138,35 @@ impl Reactor<State> for HdlReactor {
                                 self.lib.center_cursor(*key);
                                 set_handled = true;
                             }
-                            HandleState::Destroy => {
+                            HandleState::DropTarget => {
+                                self.lib
+                                    .set_border_color(*key, CONFIG.focused_background_color);
+                                set_handled = true;
+                            }
+                            HandleState::DropTargetClear => {
+                                self.lib.set_border_color(*key, CONFIG.background_color);
+                                set_handled = true;
+                            }
+                            HandleState::Restack => {
+                                set_handled = true;
+                            }
+                            HandleState::UpdateBorder => {
+                                let color = self.border_color(val, state.focus_w == *key);
+                                self.lib.set_border_color(*key, color);
+                                set_handled = true;
+                            }
+                            HandleState::Destroy if self.lib.close_client(*key) => {
+                                set_handled = true;
+                            }
+                            HandleState::Destroy | HandleState::Kill => {
                                 let windows = state
                                     .monitors
-                                    .get(&state.current_monitor)
-                                    .expect("HdlReactor - Destroy")
-                                    .get_current_windows();
+                                    .values()
+                                    .flat_map(|mon| mon.get_client_keys())
+                                    .filter(|w| w != key)
+                                    .collect();
                                 self.kill_window(*key, windows);
+                                self.properties.borrow_mut().remove(key);
 
                                 if let None = mon.get_newest() {
                                     let _ = self.tx.send(InternalAction::Focus);
@@ -135,16 +179,30 @@ impl Reactor<State> for HdlReactor {
                         });
                     if set_handled {
                         val.handle_state.replace(HandleState::Handled.into());
+                        let desktop = if val.is_sticky { ALL_DESKTOPS } else { ws.tag };
+                        self.update_client_properties(
+                            val,
+                            desktop,
+                            ws.tag == mon.current_ws && !val.is_minimized,
+                        );
+                        restacked = true;
                     }
                 });
                 self.lib.flush();
             });
         });
+        if restacked {
+            wm::restack(state);
+        }
     }
 }
-impl HdlReactor {
-    pub fn new(lib: Rc<XlibWrapper>, tx: Sender<InternalAction>) -> Self {
-        Self { lib, tx }
+impl<W: WindowSystem> HdlReactor<W> {
+    pub fn new(lib: Rc<W>, tx: ActionSender) -> Self {
+        Self {
+            lib,
+            tx,
+            properties: RefCell::new(HashMap::new()),
+        }
     }
 
     fn subscribe_to_events(&self, w: Window) {
@@ -181,13 +239,90 @@ impl HdlReactor {
     fn grab_keys(&self, w: Window) {
         let _keys = vec![
             "q", "Left", "Up", "Right", "Down", "Return", "c", "d", "e", "f", "h", "j", "k", "l",
-            "m", "r", "1", "2", "3", "4", "5", "6", "7", "8", "9",
+            "m", "n", "r", "s", "x", "space", "1", "2", "3", "4", "5", "6", "7", "8", "9",
         ]
         .iter()
         .map(|key| keysym_lookup::into_keysym(key).expect("Core: no such key"))
         .for_each(|key_sym| self.lib.grab_keys(w, key_sym, Mod4Mask | Shift));
     }
 
+    /*
+     * Keeps WM_STATE, _NET_WM_DESKTOP, _NET_FRAME_EXTENTS and the _NET_WM_STATE flags
+     * hadlock is in charge of in line with ww, other flags set by the client are kept.
+     * Windows of hidden workspaces are iconic but only minimized ones are HIDDEN,
+     * pagers would show the others as minimized
+     */
+    fn update_client_properties(&self, ww: &WindowWrapper, desktop: u32, visible: bool) {
+        let w = ww.window();
+        let properties = ClientProperties {
+            desktop,
+            visible,
+            minimized: ww.is_minimized,
+            state: ww.current_state,
+            snap_zone: ww.snap_zone,
+            layer: ww.get_layer(),
+            sticky: ww.is_sticky,
+            decorated: ww.is_decorated(),
+        };
+        if self.properties.borrow_mut().insert(w, properties) == Some(properties) {
+            return;
+        }
+        let xatom = self.lib.xatom();
+        self.lib.set_wm_state(w, visible);
+        self.lib.set_net_wm_desktop(w, desktop);
+
+        let border = match ww.current_state {
+            WindowState::Maximized | WindowState::Monocle => 0,
+            _ => CONFIG.border_width,
+        };
+        let top = if ww.is_decorated() {
+            border + CONFIG.decoration_height
+        } else {
+            border
+        };
+        self.lib.set_frame_extents(w, border, border, top, border);
+
+        let managed = [
+            xatom.NetWMStateHidden,
+            xatom.NetWMStateMaximizedVert,
+            xatom.NetWMStateMaximizedHorz,
+            xatom.NetWMStateFullscreen,
+            xatom.NetWMStateAbove,
+            xatom.NetWMStateBelow,
+            xatom.NetWMStateSticky,
+        ];
+        let mut states = self.lib.get_window_states_atoms(w);
+        states.retain(|state| !managed.contains(state));
+        if ww.is_minimized {
+            states.push(xatom.NetWMStateHidden);
+        }
+        match (ww.current_state, ww.snap_zone) {
+            (WindowState::Maximized, _) => {
+                states.push(xatom.NetWMStateMaximizedVert);
+                states.push(xatom.NetWMStateMaximizedHorz);
+            }
+            (WindowState::Monocle, _) => states.push(xatom.NetWMStateFullscreen),
+            (WindowState::Snapped, Some(zone)) => {
+                if zone.vertical.is_none() {
+                    states.push(xatom.NetWMStateMaximizedVert);
+                }
+                if zone.horizontal.is_none() {
+                    states.push(xatom.NetWMStateMaximizedHorz);
+                }
+            }
+            _ => (),
+        }
+        if ww.is_sticky {
+            states.push(xatom.NetWMStateSticky);
+        }
+        match ww.get_layer() {
+            Layer::Above => states.push(xatom.NetWMStateAbove),
+            Layer::Below => states.push(xatom.NetWMStateBelow),
+            _ => (),
+        }
+        self.lib.set_window_states_atoms(w, states);
+    }
+
     fn set_focus(&self, focus: Window, ww: &WindowWrapper) {
         if focus == self.lib.get_root() {
             return;
@@ -202,7 +337,7 @@ impl HdlReactor {
         {
             self.lib.set_border_width(focus, CONFIG.border_width as u32);
         }
-        self.lib.set_border_color(focus, CONFIG.border_color);
+        self.lib.set_border_color(focus, self.border_color(ww, true));
         self.lib.sync(false);
     }
 
@@ -211,12 +346,22 @@ impl HdlReactor {
         self.lib.sync(false);
         //self.lib.ungrab_keys(w);
         //self.lib.sync(false);
-        self.lib.set_border_color(w, CONFIG.background_color);
+        self.lib.set_border_color(w, self.border_color(ww, false));
         self.lib.resize_window(w, ww.get_size());
         self.lib.remove_focus(w);
         self.lib.sync(false);
     }
 
+    fn border_color(&self, ww: &WindowWrapper, focused: bool) -> Color {
+        if ww.not_responding {
+            CONFIG.not_responding_color
+        } else if focused {
+            CONFIG.border_color
+        } else {
+            CONFIG.background_color
+        }
+    }
+
     pub fn kill_window(&self, w: Window, clients: Vec<Window>) {
         if w == self.lib.get_root() {
             return;
@@ -55,24 +55,6 @@ impl ColumnMaster {
             None => (size, pos),
         }
     }
-
-    fn column_height(&self, screen: &Screen, dock: &DockArea, column: &Vec<&WindowWrapper>) -> i32 {
-        let dock_height = match dock.as_rect(screen) {
-            Some(dock_rect) => dock_rect.get_size().height,
-            None => 0,
-        };
-
-        let mut ret = ((screen.height - dock_height - 2 * CONFIG.outer_gap)
-            / if column.len() > 1 {
-                column.len() as i32
-            } else {
-                1
-            })
-            - 2 * CONFIG.border_width;
-
-        if column.len() > 2 { ret -= ((column.len() as i32 - 1).abs() * CONFIG.inner_gap ) / column.len() as i32 }
-        ret
-    }
 }
 
 impl Default for ColumnMaster {
@@ -96,6 +78,7 @@ impl Layout for ColumnMaster {
         screen: &Screen,
         w: Window,
         windows: Vec<&WindowWrapper>,
+        proportions: &Proportions,
     ) -> Vec<(Window, Rect)> {
         debug!("Incoming window vector in column_master: {:#?}", windows);
         let windows = windows.into_iter().collect::<Vec<&WindowWrapper>>();
@@ -107,9 +90,12 @@ impl Layout for ColumnMaster {
 
         let mut ret_vec = Vec::<(Window, Rect)>::new();
 
+        let split_x = screen.x + (screen.width as f32 * proportions.master_ratio) as i32;
+        let master_width =
+            split_x - screen.x - CONFIG.outer_gap - CONFIG.inner_gap / 2 - 2 * CONFIG.border_width;
+        let column_x = split_x + CONFIG.inner_gap / 2;
         let column_width =
-            ((screen.width / 2) - 2 * CONFIG.border_width) - (CONFIG.outer_gap + (CONFIG.inner_gap / 2));
-        let column_x = (screen.x + screen.width / 2) + CONFIG.inner_gap;
+            screen.x + screen.width - CONFIG.outer_gap - column_x - 2 * CONFIG.border_width;
 
         if windows.is_empty() {
             let (size, pos) = self.column_maximize(w, &screen, &dock_area);
@@ -117,7 +103,7 @@ impl Layout for ColumnMaster {
             return ret_vec;
         } else {
             let size = Size {
-                width: column_width,
+                width: master_width,
                 height: screen.height - dock_height - 2 * CONFIG.border_width - 2 * CONFIG.outer_gap,
             };
             let pos = Position {
@@ -128,18 +114,32 @@ impl Layout for ColumnMaster {
                 .into_iter()
                 .filter(|win| w != win.window())
                 .collect::<Vec<&WindowWrapper>>();
+
+            let column_height = screen.height
+                - dock_height
+                - 2 * CONFIG.outer_gap
+                - (windows.len() as i32 - 1) * CONFIG.inner_gap;
+            let total_weight = windows
+                .iter()
+                .map(|win| proportions.weight(win.window()))
+                .sum::<f32>();
+
+            let mut y = screen.y + dock_height + CONFIG.outer_gap;
+            let mut height_left = column_height;
             for (index, win) in windows.iter().enumerate() {
-                let pos = Position {
-                    x: column_x,
-                    y: ((screen.y + dock_height)
-                        + self.column_height(&screen, &dock_area, &windows) * index as i32)
-                        + (2 * CONFIG.border_width) * index as i32 + CONFIG.outer_gap + (CONFIG.inner_gap * index as i32),
+                let height = if index == windows.len() - 1 {
+                    height_left
+                } else {
+                    (column_height as f32 * proportions.weight(win.window()) / total_weight) as i32
                 };
+                let pos = Position { x: column_x, y };
                 let size = Size {
                     width: column_width,
-                    height: self.column_height(&screen, &dock_area, &windows),
+                    height: height - 2 * CONFIG.border_width,
                 };
-                ret_vec.push((win.window(), Rect::new(pos, size)))
+                ret_vec.push((win.window(), Rect::new(pos, size)));
+                y += height + CONFIG.inner_gap;
+                height_left -= height;
             }
             ret_vec.push((w, Rect::new(pos, size)));
         }
@@ -190,6 +190,7 @@ impl Layout for ColumnMaster {
         screen: &Screen,
         dock_area: &DockArea,
         windows: Vec<WindowWrapper>,
+        proportions: &Proportions,
     ) -> Vec<(Window, Rect)> {
         let mut windows = windows.iter().collect::<Vec<&WindowWrapper>>();
 
@@ -200,7 +201,7 @@ impl Layout for ColumnMaster {
                 Some(ww) => ww.window(),
                 _ => focus,
             };
-            self.place_window(&dock_area, &screen, focus, windows)
+            self.place_window(dock_area, screen, focus, windows, proportions)
         }
     }
 
@@ -4,6 +4,7 @@ use crate::{
     config::*,
     models::{
         dockarea::DockArea, rect::Rect, screen::Screen, windowwrapper::WindowWrapper, Direction,
+        SnapZone,
     },
     xlibwrapper::{
         util::{Position, Size},
@@ -47,6 +48,7 @@ impl Layout for Floating {
         screen: &Screen,
         w: Window,
         windows: Vec<&WindowWrapper>,
+        proportions: &Proportions,
     ) -> Vec<(Window, Rect)> {
         let new_size = Size {
             width: (screen.width / 10) * 8,
@@ -112,6 +114,7 @@ impl Layout for Floating {
         screen: &Screen,
         dock_area: &DockArea,
         windows: Vec<WindowWrapper>,
+        proportions: &Proportions,
     ) -> Vec<(Window, Rect)> {
         let space_rect = match dock_area.as_rect(screen) {
             Some(dock) => Rect::new(
@@ -241,127 +244,60 @@ impl Layout for Floating {
         w: Window,
         direction: Direction,
     ) -> Vec<WindowWrapper> {
-        let origin = Position {
-            x: screen.x,
-            y: screen.y,
+        let zone = SnapZone::shift(ww.snap_zone, direction);
+        let (pos, size) = self.snap(screen, dock_area, ww, zone);
+        let (restore_position, restore_size) = match ww.snap_zone {
+            Some(_) => (ww.restore_position, ww.restore_size),
+            None => (ww.get_position(), ww.get_size()),
         };
-        match direction {
-            Direction::North => {
-                let pos = self
-                    .move_window(&screen, &dock_area, w, true, screen.x, screen.y)
-                    .0;
-                let size = if ww.is_decorated() {
-                    Size {
-                        width: screen.width - 2 * CONFIG.border_width,
-                        height: (screen.height) / 2 - 2 * CONFIG.border_width,
-                    }
-                } else {
-                    Size {
-                        width: screen.width - 2 * CONFIG.border_width,
-                        height: (screen.height) / 2 - 2 * CONFIG.border_width,
-                    }
-                };
-                let mut size =
-                    Floating::get_size(&ww, self.resize_window(&ww, w, size.width, size.height));
-                if let Some(dock) = dock_area.as_rect(&screen) {
-                    size.height -= dock.get_size().height / 2
-                }
-                vec![WindowWrapper {
-                    window_rect: Rect::new(pos, size),
-                    ..ww.clone()
-                }]
-            }
-            Direction::East => {
-                let pos = self
-                    .move_window(
-                        &screen,
-                        &dock_area,
-                        w,
-                        true,
-                        screen.x + screen.width / 2,
-                        screen.y,
-                    )
-                    .0;
-                let size = if ww.is_decorated() {
-                    Size {
-                        width: (screen.width) / 2 - 2 * CONFIG.border_width,
-                        height: screen.height - CONFIG.border_width,
-                    }
-                } else {
-                    Size {
-                        width: (screen.width) / 2 - 2 * CONFIG.border_width,
-                        height: screen.height - CONFIG.border_width,
-                    }
-                };
-                let mut size =
-                    Floating::get_size(&ww, self.resize_window(&ww, w, size.width, size.height));
-                if let Some(dock) = dock_area.as_rect(&screen) {
-                    size.height -= dock.get_size().height;
-                }
-                vec![WindowWrapper {
-                    window_rect: Rect::new(pos, size),
-                    ..ww.clone()
-                }]
-            }
-            Direction::West => {
-                let pos = self
-                    .move_window(&screen, &dock_area, w, true, screen.x, screen.y)
-                    .0;
-                let size = if ww.is_decorated() {
-                    Size {
-                        width: (screen.width) / 2 - 2 * CONFIG.border_width,
-                        height: (screen.height) - CONFIG.border_width,
-                    }
-                } else {
-                    Size {
-                        width: (screen.width) / 2 - 2 * CONFIG.border_width,
-                        height: (screen.height) - CONFIG.border_width,
-                    }
-                };
-                let mut size =
-                    Floating::get_size(&ww, self.resize_window(&ww, w, size.width, size.height));
-                if let Some(dock) = dock_area.as_rect(&screen) {
-                    size.height -= dock.get_size().height;
-                }
-                vec![WindowWrapper {
-                    window_rect: Rect::new(pos, size),
-                    ..ww.clone()
-                }]
-            }
-            Direction::South => {
-                let mut pos = self
-                    .move_window(
-                        &screen,
-                        &dock_area,
-                        w,
-                        true,
-                        screen.x,
-                        screen.height / 2 - CONFIG.border_width,
-                    )
-                    .0;
-                let size = if ww.is_decorated() {
-                    Size {
-                        width: screen.width - 2 * CONFIG.border_width,
-                        height: (screen.height) / 2 - 2 * CONFIG.border_width,
-                    }
-                } else {
-                    Size {
-                        width: screen.width - 2 * CONFIG.border_width,
-                        height: (screen.height) / 2 - CONFIG.border_width,
-                    }
-                };
-                let mut size =
-                    Floating::get_size(&ww, self.resize_window(&ww, w, size.width, size.height));
-                if let Some(dock) = dock_area.as_rect(&screen) {
-                    let offset = dock.get_size().height / 2;
-                    size.height -= offset;
-                    pos.y += offset as i32;
-                }
-                vec![WindowWrapper {
-                    window_rect: Rect::new(pos, size),
-                    ..ww.clone()
-                }]
-            }
-        }
+        vec![WindowWrapper {
+            window_rect: Rect::new(pos, size),
+            restore_position,
+            restore_size,
+            snap_zone: Some(zone),
+            ..ww.clone()
+        }]
+    }
+
+    fn snap(
+        &self,
+        screen: &Screen,
+        dock_area: &DockArea,
+        ww: &WindowWrapper,
+        zone: SnapZone,
+    ) -> (Position, Size) {
+        let dock_height = match dock_area.as_rect(screen) {
+            Some(dock) => dock.get_size().height,
+            None => 0,
+        };
+        let (area_y, area_height) = (screen.y + dock_height, screen.height - dock_height);
+
+        let (x, width) = match zone.horizontal {
+            Some(Direction::West) => (screen.x, screen.width / 2),
+            Some(Direction::East) => (
+                screen.x + screen.width / 2,
+                screen.width - screen.width / 2,
+            ),
+            _ => (screen.x, screen.width),
+        };
+        let (y, height) = match zone.vertical {
+            Some(Direction::North) => (area_y, area_height / 2),
+            Some(Direction::South) => (
+                area_y + area_height / 2,
+                area_height - area_height / 2,
+            ),
+            _ => (area_y, area_height),
+        };
+
+        let size = Floating::get_size(
+            ww,
+            self.resize_window(
+                ww,
+                ww.window(),
+                width - 2 * CONFIG.border_width,
+                height - 2 * CONFIG.border_width,
+            ),
+        );
+        (Position { x, y }, size)
     }
 }
@@ -3,10 +3,11 @@
 pub mod floating;
 pub mod column_master;
 
-use crate::models::{dockarea::DockArea, screen::Screen, windowwrapper::WindowWrapper, Direction, rect::Rect};
+use crate::models::{dockarea::DockArea, screen::Screen, windowwrapper::WindowWrapper, Direction, SnapZone, rect::Rect};
 use crate::xlibwrapper::util::{Position, Size};
 use crate::xlibwrapper::xlibmodels::Window;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum LayoutTag {
@@ -24,6 +25,31 @@ impl std::fmt::Display for LayoutTag {
     }
 }
 
+/*
+ * User adjusted split of a tiled workspace: the share of the screen width given to
+ * the master window and the relative height of each stack window
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Proportions {
+    pub master_ratio: f32,
+    pub weights: HashMap<Window, f32>,
+}
+
+impl Proportions {
+    pub fn weight(&self, w: Window) -> f32 {
+        *self.weights.get(&w).unwrap_or(&1.0)
+    }
+}
+
+impl Default for Proportions {
+    fn default() -> Self {
+        Self {
+            master_ratio: 0.5,
+            weights: HashMap::default(),
+        }
+    }
+}
+
 pub fn layout_from_tag(tag: LayoutTag) -> Box<dyn Layout> {
     match tag {
         LayoutTag::Floating => Box::new(floating::Floating::default()),
@@ -33,7 +59,7 @@ pub fn layout_from_tag(tag: LayoutTag) -> Box<dyn Layout> {
 
 pub trait Layout: std::fmt::Debug + std::fmt::Display {
 
-    fn place_window(&mut self, dock_area: &DockArea, screen: &Screen, w: Window, windows: Vec<&WindowWrapper>) -> Vec<(Window, Rect)> {
+    fn place_window(&mut self, dock_area: &DockArea, screen: &Screen, w: Window, windows: Vec<&WindowWrapper>, proportions: &Proportions) -> Vec<(Window, Rect)> {
         unimplemented!();
     }
 
@@ -59,7 +85,7 @@ pub trait Layout: std::fmt::Debug + std::fmt::Display {
         unimplemented!();
     }
     
-    fn reorder(&mut self, focus: Window, screen: &Screen, dock_area: &DockArea, windows: Vec<WindowWrapper>) -> Vec<(Window, Rect)> {
+    fn reorder(&mut self, focus: Window, screen: &Screen, dock_area: &DockArea, windows: Vec<WindowWrapper>, proportions: &Proportions) -> Vec<(Window, Rect)> {
         unimplemented!()
     }
 
@@ -103,4 +129,15 @@ pub trait Layout: std::fmt::Debug + std::fmt::Display {
     ) -> Vec<WindowWrapper> {
         unimplemented!();
     }
+
+    fn snap(
+        &self,
+        screen: &Screen,
+        dock_area: &DockArea,
+        ww: &WindowWrapper,
+        zone: SnapZone,
+    ) -> (Position, Size) {
+        // Layouts that don't snap leave the window where it is
+        (ww.get_position(), ww.get_size())
+    }
 }
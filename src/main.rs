@@ -2,43 +2,72 @@
 #[macro_use]
 extern crate log;
 
+mod cli;
 mod config;
+mod error;
 mod hdl_dispatcher;
 mod hdl_reactor;
 mod layout;
 mod models;
+mod recording;
 mod reducers;
+mod session;
 mod state;
 mod wm;
 mod xlibwrapper;
 
-use std::{process::Command, rc::Rc, sync::mpsc, thread};
+use std::{path::Path, process::{self, Command}, rc::Rc, sync::mpsc, thread};
 use xlibwrapper::core::*;
 
 use crate::config::*;
 use chrono;
 use fern;
 use nix::sys::signal::{self, SigHandler, Signal};
+use structopt::StructOpt;
 
 pub type HadlockResult<T> = Result<T, Box<dyn std::error::Error>>;
 pub type HadlockOption<T> = Option<T>;
 
 fn main() -> HadlockResult<()> {
-    init_logger()?;
+    let args = cli::Args::from_args();
+    if args.print_default_config {
+        println!("{}", serde_json::to_string_pretty(&config::config::Config::default())?);
+        return Ok(());
+    }
+    if let Some(path) = args.config_path() {
+        set_config_path(path);
+    }
+    if args.check_config {
+        check_config(args.config_path());
+    }
+    if args.convert {
+        convert_config(args.config_path());
+    }
+    if let Some(path) = std::env::var_os(recording::REPLAY_ENV) {
+        return recording::replay(Path::new(&path));
+    }
+    init_logger(&args)?;
     let (tx, rx) = mpsc::channel::<bool>();
 
-    let xlib = Rc::new(XlibWrapper::new());
+    let xlib = Rc::new(XlibWrapper::new(args.replace)?);
     info!("Screens on startup: {:?}", xlib.get_screens());
 
     // Avoid zombies by ignoring SIGCHLD
     unsafe { signal::signal(Signal::SIGCHLD, SigHandler::SigIgn) }.unwrap();
-    call_commands(ExecTime::Pre);
-    thread::spawn(move || match rx.recv() {
-        Ok(true) => call_commands(ExecTime::Post),
-        _ => return,
-    });
+    // Every thread has to block these for the signalfd of the event loop to see them
+    hdl_dispatcher::shutdown_signals().thread_block()?;
+    // A restart picks up where the old process left, the commands already ran
+    let session = session::Session::take();
+    if session.is_none() {
+        call_commands(ExecTime::Pre);
+        thread::spawn(move || {
+            if let Ok(true) = rx.recv() {
+                call_commands(ExecTime::Post);
+            }
+        });
+    }
 
-    hdl_dispatcher::run(xlib, tx);
+    hdl_dispatcher::run(xlib, tx, session);
     Ok(())
 }
 
@@ -69,8 +98,53 @@ fn call_commands(exec_time: ExecTime) {
     })
 }
 
-fn init_logger() -> HadlockResult<()> {
-    fern::Dispatch::new()
+/* Prints every problem of the config and exits, with 0 only if there are none */
+fn check_config(path: Option<std::path::PathBuf>) -> ! {
+    let path = match path.or_else(default_path) {
+        Some(path) => path,
+        None => {
+            eprintln!("No config path given and neither XDG_CONFIG_HOME nor HOME is set");
+            process::exit(1);
+        }
+    };
+    let (_, problems) = read_config(&path);
+    if problems.is_empty() {
+        println!("{}: ok", path.display());
+        process::exit(0);
+    }
+    for problem in problems {
+        eprintln!("{}: {}", path.display(), problem);
+    }
+    process::exit(1);
+}
+
+/* Writes the JSON config as TOML and exits, problems in it are printed but don't stop the conversion */
+fn convert_config(path: Option<std::path::PathBuf>) -> ! {
+    let path = match path.or_else(default_path) {
+        Some(path) => path,
+        None => {
+            eprintln!("No config path given and neither XDG_CONFIG_HOME nor HOME is set");
+            process::exit(1);
+        }
+    };
+    let (_, problems) = read_config(&path);
+    for problem in problems {
+        eprintln!("{}: {}", path.display(), problem);
+    }
+    match convert_to_toml(&path) {
+        Ok(target) => {
+            println!("Wrote {}", target.display());
+            process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Failed to convert {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
+}
+
+fn init_logger(args: &cli::Args) -> HadlockResult<()> {
+    let mut dispatch = fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
                 "{}[{}][{}] {}",
@@ -80,9 +154,11 @@ fn init_logger() -> HadlockResult<()> {
                 message
             ))
         })
-        .level(log::LogLevelFilter::Debug)
-        .chain(std::io::stderr())
-        //.chain(fern::log_file("~/hadlog")?)
-        .apply()?;
+        .level(args.log_level)
+        .chain(std::io::stderr());
+    if let Some(path) = &args.log_file {
+        dispatch = dispatch.chain(fern::log_file(path)?);
+    }
+    dispatch.apply()?;
     Ok(())
 }
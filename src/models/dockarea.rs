@@ -41,6 +41,20 @@ impl From<&[i64]> for DockArea {
 }
 
 impl DockArea {
+    /* What is left of the screen once every strut is taken away */
+    pub fn work_area(&self, s: &Screen) -> Rect {
+        Rect::new(
+            Position {
+                x: s.x + self.left,
+                y: s.y + self.top,
+            },
+            Size {
+                width: s.width - self.left - self.right,
+                height: s.height - self.top - self.bottom,
+            },
+        )
+    }
+
     pub fn as_rect(&self, s: &Screen) -> Option<Rect> {
         let screen_width = s.width as i32;
         let screen_height = s.height as i32;
@@ -112,3 +126,17 @@ impl DockArea {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn work_area_subtracts_struts() {
+        let dock: DockArea = (&[30, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0][..]).into();
+        let screen = Screen::new(0, 1000, 500, 100, 0);
+        let area = dock.work_area(&screen);
+        assert_eq!(area.get_position(), Position { x: 130, y: 20 });
+        assert_eq!(area.get_size(), Size { width: 970, height: 480 });
+    }
+}
@@ -1,7 +1,116 @@
-use crate::xlibwrapper::xlibmodels::Window;
+use {
+    crate::xlibwrapper::xlibmodels::Window,
+    nix::{fcntl::OFlag, unistd},
+    std::{
+        os::unix::io::RawFd,
+        sync::{
+            mpsc::{self, Receiver, SendError, Sender},
+            Arc, Mutex,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+#[derive(Debug)]
 pub enum InternalAction {
     Focus,
     FocusSpecific(Window),
     Destroy(Window),
+    Kill(Window, i64),
+    PingFocused,
+    PingTimeout(Window, i64),
+    Restore(Window),
+    Restart,
     UpdateLayout
 }
+
+type Timers = Arc<Mutex<Vec<(Instant, InternalAction)>>>;
+
+/*
+ * Internal actions come through an mpsc channel, every send also writes to a
+ * pipe so the event loop can poll for them next to the X connection
+ */
+pub fn channel() -> nix::Result<(ActionSender, ActionReceiver)> {
+    let (read, write) = unistd::pipe2(OFlag::O_NONBLOCK | OFlag::O_CLOEXEC)?;
+    let (tx, rx) = mpsc::channel();
+    let timers = Timers::default();
+    Ok((
+        ActionSender {
+            tx,
+            timers: timers.clone(),
+            wake: write,
+        },
+        ActionReceiver {
+            rx,
+            timers,
+            wake: read,
+        },
+    ))
+}
+
+#[derive(Clone, Debug)]
+pub struct ActionSender {
+    tx: Sender<InternalAction>,
+    timers: Timers,
+    wake: RawFd,
+}
+
+impl ActionSender {
+    pub fn send(&self, action: InternalAction) -> Result<(), SendError<InternalAction>> {
+        self.tx.send(action)?;
+        self.wake();
+        Ok(())
+    }
+
+    /* Sends action once delay has passed, the event loop wakes up for it */
+    pub fn send_after(&self, delay: Duration, action: InternalAction) {
+        if let Ok(mut timers) = self.timers.lock() {
+            timers.push((Instant::now() + delay, action));
+        }
+        self.wake();
+    }
+
+    fn wake(&self) {
+        // A full pipe already wakes the loop
+        let _ = unistd::write(self.wake, &[0]);
+    }
+}
+
+pub struct ActionReceiver {
+    rx: Receiver<InternalAction>,
+    timers: Timers,
+    wake: RawFd,
+}
+
+impl ActionReceiver {
+    pub fn wake_fd(&self) -> RawFd {
+        self.wake
+    }
+
+    /* Everything sent so far followed by the timers that are due */
+    pub fn drain(&self) -> Vec<InternalAction> {
+        let mut buf = [0; 64];
+        while let Ok(n) = unistd::read(self.wake, &mut buf) {
+            if n == 0 {
+                break;
+            }
+        }
+
+        let mut actions = self.rx.try_iter().collect::<Vec<_>>();
+        if let Ok(mut timers) = self.timers.lock() {
+            let now = Instant::now();
+            let (due, pending): (Vec<_>, Vec<_>) =
+                timers.drain(..).partition(|(at, _)| *at <= now);
+            *timers = pending;
+            actions.extend(due.into_iter().map(|(_, action)| action));
+        }
+        actions
+    }
+
+    /* Time until the next timer is due, None if there is none */
+    pub fn next_timeout(&self) -> Option<Duration> {
+        let timers = self.timers.lock().ok()?;
+        let next = timers.iter().map(|(at, _)| *at).min()?;
+        Some(next.saturating_duration_since(Instant::now()))
+    }
+}
@@ -7,9 +7,10 @@ pub mod windowwrapper;
 pub mod workspace;
 pub mod internal_action;
 
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum WindowState {
     Snapped,
     Maximized,
@@ -18,7 +19,7 @@ pub enum WindowState {
     Tiled,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     North,
     West,
@@ -26,6 +27,149 @@ pub enum Direction {
     South,
 }
 
+/*
+ * Part of a screen a floating window is tiled to. A direction on one axis
+ * gives a half, on both axes a quarter and on neither the whole screen.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapZone {
+    pub horizontal: Option<Direction>,
+    pub vertical: Option<Direction>,
+}
+
+const SNAP_EDGE: i32 = 5;
+
+impl SnapZone {
+    pub fn full() -> Self {
+        Self {
+            horizontal: None,
+            vertical: None,
+        }
+    }
+
+    /*
+     * Keyboard snapping: sets the axis of direction and keeps the other one,
+     * pressing the same direction again goes back from a quarter to a half
+     */
+    pub fn shift(zone: Option<SnapZone>, direction: Direction) -> Self {
+        let zone = zone.unwrap_or_else(SnapZone::full);
+        match direction {
+            Direction::West | Direction::East if zone.horizontal == Some(direction) => Self {
+                horizontal: Some(direction),
+                vertical: None,
+            },
+            Direction::West | Direction::East => Self {
+                horizontal: Some(direction),
+                ..zone
+            },
+            Direction::North | Direction::South if zone.vertical == Some(direction) => Self {
+                horizontal: None,
+                vertical: Some(direction),
+            },
+            Direction::North | Direction::South => Self {
+                vertical: Some(direction),
+                ..zone
+            },
+        }
+    }
+
+    /*
+     * Zone under the pointer while dragging: left/right edges give halves,
+     * the top edge the whole screen and the corners quarters
+     */
+    pub fn at_point(screen: &screen::Screen, x: i32, y: i32) -> Option<Self> {
+        let left = x <= screen.x + SNAP_EDGE;
+        let right = x >= screen.x + screen.width - 1 - SNAP_EDGE;
+        let top = y <= screen.y + SNAP_EDGE;
+        let bottom = y >= screen.y + screen.height - 1 - SNAP_EDGE;
+
+        let near_left = x < screen.x + screen.width / 5;
+        let near_right = x > screen.x + screen.width - screen.width / 5;
+        let near_top = y < screen.y + screen.height / 5;
+        let near_bottom = y > screen.y + screen.height - screen.height / 5;
+
+        if left || right {
+            let vertical = if near_top {
+                Some(Direction::North)
+            } else if near_bottom {
+                Some(Direction::South)
+            } else {
+                None
+            };
+            return Some(Self {
+                horizontal: Some(if left { Direction::West } else { Direction::East }),
+                vertical,
+            });
+        }
+
+        if top || bottom {
+            let horizontal = if near_left {
+                Some(Direction::West)
+            } else if near_right {
+                Some(Direction::East)
+            } else {
+                None
+            };
+            return match horizontal {
+                Some(_) => Some(Self {
+                    horizontal,
+                    vertical: Some(if top { Direction::North } else { Direction::South }),
+                }),
+                None if top => Some(Self::full()),
+                None => None,
+            };
+        }
+        None
+    }
+}
+
+/* Corner or edge a window is resized from */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    NorthWest,
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+}
+
+impl Corner {
+    /* The corner of rect closest to the point (x, y) */
+    pub fn nearest(rect: &rect::Rect, x: i32, y: i32) -> Self {
+        let pos = rect.get_position();
+        let size = rect.get_size();
+        let west = x < pos.x + size.width / 2;
+        let north = y < pos.y + size.height / 2;
+        match (north, west) {
+            (true, true) => Corner::NorthWest,
+            (true, false) => Corner::NorthEast,
+            (false, true) => Corner::SouthWest,
+            (false, false) => Corner::SouthEast,
+        }
+    }
+
+    /* -1 if the west side moves, 1 if the east side moves, 0 for neither */
+    pub fn horizontal(self) -> i32 {
+        match self {
+            Corner::NorthWest | Corner::West | Corner::SouthWest => -1,
+            Corner::NorthEast | Corner::East | Corner::SouthEast => 1,
+            Corner::North | Corner::South => 0,
+        }
+    }
+
+    /* -1 if the north side moves, 1 if the south side moves, 0 for neither */
+    pub fn vertical(self) -> i32 {
+        match self {
+            Corner::NorthWest | Corner::North | Corner::NorthEast => -1,
+            Corner::SouthWest | Corner::South | Corner::SouthEast => 1,
+            Corner::West | Corner::East => 0,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum HandleState {
     New,
@@ -35,6 +179,7 @@ pub enum HandleState {
     Focus,
     Unfocus,
     Destroy,
+    Kill,
     Move,
     Center,
     Shift,
@@ -43,9 +188,24 @@ pub enum HandleState {
     MaximizeRestore,
     Monocle,
     MonocleRestore,
+    DropTarget,
+    DropTargetClear,
+    Restack,
+    UpdateBorder,
     UpdateLayout,
 }
 
+/* Stacking layers from the bottom up, windows never leave their layer when raised */
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum Layer {
+    Desktop,
+    Below,
+    Normal,
+    Above,
+    Fullscreen,
+    Dock,
+}
+
 impl From<HandleState> for Vec<HandleState> {
     fn from(w: HandleState) -> Vec<HandleState> {
         vec![w]
@@ -57,3 +217,65 @@ impl From<HandleState> for RefCell<Vec<HandleState>> {
         RefCell::new(vec![w])
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snap_zone_shift() {
+        let left = SnapZone::shift(None, Direction::West);
+        assert_eq!(left.horizontal, Some(Direction::West));
+        assert_eq!(left.vertical, None);
+
+        let top_left = SnapZone::shift(Some(left), Direction::North);
+        assert_eq!(top_left.horizontal, Some(Direction::West));
+        assert_eq!(top_left.vertical, Some(Direction::North));
+
+        assert_eq!(SnapZone::shift(Some(top_left), Direction::West), left);
+    }
+
+    #[test]
+    fn snap_zone_at_point() {
+        let screen = screen::Screen::new(0, 1000, 500, 0, 0);
+        assert_eq!(SnapZone::at_point(&screen, 500, 250), None);
+        assert_eq!(SnapZone::at_point(&screen, 500, 0), Some(SnapZone::full()));
+        assert_eq!(
+            SnapZone::at_point(&screen, 999, 250),
+            Some(SnapZone {
+                horizontal: Some(Direction::East),
+                vertical: None
+            })
+        );
+        assert_eq!(
+            SnapZone::at_point(&screen, 0, 499),
+            Some(SnapZone {
+                horizontal: Some(Direction::West),
+                vertical: Some(Direction::South)
+            })
+        );
+        assert_eq!(SnapZone::at_point(&screen, 500, 499), None);
+    }
+
+    #[test]
+    fn window_layers() {
+        let rect = rect::Rect::new(
+            crate::xlibwrapper::util::Position { x: 0, y: 0 },
+            crate::xlibwrapper::util::Size {
+                width: 10,
+                height: 10,
+            },
+        );
+        let mut ww = windowwrapper::WindowWrapper::new(1, rect, false);
+        assert_eq!(ww.get_layer(), Layer::Normal);
+
+        ww.is_floating = true;
+        assert_eq!(ww.get_layer(), Layer::Above);
+        ww.layer = Layer::Below;
+        assert_eq!(ww.get_layer(), Layer::Below);
+
+        ww.current_state = WindowState::Monocle;
+        assert_eq!(ww.get_layer(), Layer::Fullscreen);
+        assert!(Layer::Fullscreen < Layer::Dock);
+    }
+}
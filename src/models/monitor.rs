@@ -7,7 +7,7 @@ use crate::{
 };
 use super::{
     dockarea::DockArea, rect::Rect, screen::Screen, windowwrapper::WindowWrapper,
-    workspace::Workspace, Direction, HandleState,
+    workspace::Workspace, Direction, HandleState, SnapZone,
 };
 use std::collections::HashMap;
 use std::cell::RefCell;
@@ -45,6 +45,10 @@ impl Monitor {
         self.dock_area = dock_area;
     }
 
+    pub fn work_area(&self) -> Rect {
+        self.dock_area.work_area(&self.screen)
+    }
+
     pub fn add_window(&mut self, w: Window, ww: WindowWrapper) {
         match self.workspaces.get_mut(&self.current_ws) {
             Some(ws) => ws.add_window(w, ww),
@@ -64,6 +68,10 @@ impl Monitor {
         self.workspaces.get(&self.current_ws)?.get_newest()
     }
 
+    pub fn get_master(&self) -> Option<(&Window, &WindowWrapper)> {
+        self.workspaces.get(&self.current_ws)?.get_master()
+    }
+
     pub fn get_previous(&self, win: Window) -> Option<&WindowWrapper> {
 
         let ws = self.workspaces.get(&self.current_ws)?;
@@ -166,9 +174,18 @@ impl Monitor {
         let screen = self.screen.clone();
         let dock_area = self.dock_area.clone();
         let ws = self.get_current_ws_mut().expect("monitor: place_window 2");
-        let windows = ws.clients.values().collect::<Vec<&WindowWrapper>>();
-        ws.layout
-            .place_window(&dock_area.clone(), &screen.clone(), w, windows)
+        let windows = ws
+            .clients
+            .values()
+            .filter(|ww| ww.tiles())
+            .collect::<Vec<&WindowWrapper>>();
+        ws.layout.place_window(
+            &dock_area.clone(),
+            &screen.clone(),
+            w,
+            windows,
+            &ws.proportions,
+        )
     }
 
     pub fn move_window(&mut self, w: Window, x: i32, y: i32) -> (Position, Position) {
@@ -183,10 +200,9 @@ impl Monitor {
     pub fn reorder(&mut self, focus: Window, windows: &Vec<WindowWrapper>) -> Vec<(Window, Rect)> {
         let screen = self.screen.clone();
         let dock_area = self.dock_area.clone();
-        self.get_current_ws_mut()
-            .expect("Monitor: reorder")
-            .layout
-            .reorder(focus, &screen, &dock_area, windows.clone())
+        let ws = self.get_current_ws_mut().expect("Monitor: reorder");
+        ws.layout
+            .reorder(focus, &screen, &dock_area, windows.clone(), &ws.proportions)
     }
 
     pub fn resize_window(&mut self, w: Window, width: i32, height: i32) -> (Size, Size) {
@@ -218,6 +234,38 @@ impl Monitor {
             .monocle(&screen, &dock_area, &ww, w)
     }
 
+    pub fn snap(&self, ww: &WindowWrapper, zone: SnapZone) -> (Position, Size) {
+        let screen = self.screen.clone();
+        let dock_area = self.dock_area.clone();
+        self.get_current_ws()
+            .expect("monitor: snap")
+            .layout
+            .snap(&screen, &dock_area, ww, zone)
+    }
+
+    /* Outer rects of the visible windows in the current workspace, except w */
+    pub fn get_snap_targets(&self, w: Window) -> Vec<Rect> {
+        let border = crate::config::CONFIG.border_width;
+        match self.get_current_ws() {
+            Some(ws) => ws
+                .clients
+                .values()
+                .filter(|ww| ww.window() != w)
+                .map(|ww| {
+                    let size = ww.get_size();
+                    Rect::new(
+                        ww.get_position(),
+                        Size {
+                            width: size.width + 2 * border,
+                            height: size.height + 2 * border,
+                        },
+                    )
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+
     pub fn shift_window(&mut self, w: Window, direction: Direction) -> Vec<WindowWrapper> {
         let ww = self.get_client(w).expect("monitor: shift_window 1").clone();
         let screen = self.screen.clone();
@@ -1,8 +1,9 @@
 #![allow(unused_variables, dead_code)]
 use crate::xlibwrapper::util::*;
 use crate::xlibwrapper::xlibmodels::Geometry;
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Rect {
     position: Position,
     size: Size,
@@ -1,10 +1,11 @@
 #![allow(dead_code)]
 use crate::xlibwrapper::xlibmodels::WindowAttributes as WinAttr;
+use serde::{Deserialize, Serialize};
 use std::convert::From;
 use x11_dl::xinerama::XineramaScreenInfo as XSInfo;
 use x11_dl::xlib::Window;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Screen {
     pub root: Window,
     pub height: i32,
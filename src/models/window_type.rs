@@ -9,6 +9,7 @@ pub enum WindowType {
     Utility,
     Splash,
     Dialog,
+    Notification,
     Normal,
 }
 
@@ -22,6 +23,7 @@ impl WindowType {
             WindowType::Menu => "Menu".into(),
             WindowType::Splash => "Splash".into(),
             WindowType::Utility => "Utility".into(),
+            WindowType::Notification => "Notification".into(),
             WindowType::Normal => "Normal".into(),
             _ => "Unknown".into(),
         }
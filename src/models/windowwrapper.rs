@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 use super::rect::*;
 use super::HandleState;
+use super::Layer;
+use super::SnapZone;
 use super::WindowState;
 use crate::xlibwrapper::util::*;
 use crate::xlibwrapper::xlibmodels::*;
@@ -14,12 +16,18 @@ pub struct WindowWrapper {
     pub window_rect: Rect,
     pub is_visible: bool,
     pub is_trans: bool,
+    pub is_floating: bool,
+    pub is_sticky: bool,
+    pub is_minimized: bool,
+    pub not_responding: bool,
     pub dec_rect: Option<Rect>,
     pub restore_position: Position,
     pub restore_size: Size,
     pub handle_state: RefCell<Vec<HandleState>>,
     pub current_state: WindowState,
     pub previous_state: WindowState,
+    pub snap_zone: Option<SnapZone>,
+    pub layer: Layer,
     pub toc: Instant
 }
 
@@ -32,16 +40,41 @@ impl WindowWrapper {
             window_rect,
             is_visible: true,
             is_trans,
+            is_floating: false,
+            is_sticky: false,
+            is_minimized: false,
+            not_responding: false,
             dec_rect: None,
             restore_position: Position { x: 0, y: 0 },
             restore_size,
             handle_state: RefCell::new(vec![HandleState::New]),
             current_state: WindowState::Free,
             previous_state: WindowState::Free,
+            snap_zone: None,
+            layer: Layer::Normal,
             toc: Instant::now()
         }
     }
 
+    /* Kept out of the tiling, toggled floating or sticky */
+    pub fn floats(&self) -> bool {
+        self.is_floating || self.is_sticky
+    }
+
+    /* Part of the tiling input of its workspace */
+    pub fn tiles(&self) -> bool {
+        !self.floats() && !self.is_minimized
+    }
+
+    /* Layer the window is stacked in right now, fullscreen and floating windows are lifted */
+    pub fn get_layer(&self) -> Layer {
+        match self.current_state {
+            WindowState::Monocle => Layer::Fullscreen,
+            _ if self.floats() && self.layer == Layer::Normal => Layer::Above,
+            _ => self.layer,
+        }
+    }
+
     pub fn get_window_state(&self) -> WindowState {
         self.current_state.clone()
     }
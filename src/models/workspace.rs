@@ -3,7 +3,7 @@ use indexmap::IndexMap;
 
 use crate::{
     config::*,
-    layout::{self, column_master, floating, Layout, LayoutTag},
+    layout::{self, column_master, floating, Layout, LayoutTag, Proportions},
     models::windowwrapper::WindowWrapper,
     xlibwrapper::xlibmodels::Window,
 };
@@ -13,6 +13,7 @@ pub struct Workspace {
     pub tag: u32,
     pub clients: IndexMap<Window, WindowWrapper>,
     pub layout: Box<dyn Layout>,
+    pub proportions: Proportions,
     available_layouts: Vec<LayoutTag>,
     current_tag: LayoutTag,
 }
@@ -23,6 +24,7 @@ impl Workspace {
             tag,
             clients: Default::default(),
             layout: layout::layout_from_tag(CONFIG.default_layout),
+            proportions: Proportions::default(),
             available_layouts: vec![LayoutTag::Floating, LayoutTag::ColumnMaster],
             current_tag: CONFIG.default_layout,
         }
@@ -32,6 +34,11 @@ impl Workspace {
         self.current_tag
     }
 
+    pub fn set_layout(&mut self, tag: LayoutTag) {
+        self.current_tag = tag;
+        self.layout = layout::layout_from_tag(tag);
+    }
+
     pub fn circulate_layout(&mut self) {
         let index = self
             .available_layouts
@@ -68,6 +75,11 @@ impl Workspace {
         self.clients.iter().last()
     }
 
+    /* Newest window that is tiled, floating windows are never master */
+    pub fn get_master(&self) -> Option<(&Window, &WindowWrapper)> {
+        self.clients.iter().filter(|(_, ww)| ww.tiles()).last()
+    }
+
     pub fn get_previous(&self, ww: &WindowWrapper) -> Option<&WindowWrapper> {
         if self.clients.len() <= 1 {
             return None;
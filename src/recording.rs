@@ -0,0 +1,261 @@
+use {
+    crate::{
+        hdl_reactor::HdlReactor,
+        models::{internal_action, screen::Screen},
+        state::State,
+        xlibwrapper::{
+            action,
+            fake::{FakeWindow, FakeWindowSystem},
+            window_system::WindowSystem,
+            xlibmodels::*,
+        },
+        HadlockResult,
+    },
+    reducer::*,
+    serde::{Deserialize, Serialize},
+    std::{
+        env,
+        fs::File,
+        io::{self, BufRead, BufReader, Write},
+        path::Path,
+        rc::Rc,
+        time::Instant,
+    },
+};
+
+/* Path of the file to record a session to */
+pub const RECORD_ENV: &str = "HADLOCK_RECORD";
+/* Path of a recording to replay instead of managing the display */
+pub const REPLAY_ENV: &str = "HADLOCK_REPLAY";
+
+/* Every action the dispatcher hands to the store */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "action")]
+pub enum Dispatched {
+    ConfigurationRequest(action::ConfigurationRequest),
+    MapRequest(action::MapRequest),
+    UnmapNotify(action::UnmapNotify),
+    ButtonPress(action::ButtonPress),
+    ButtonRelease(action::ButtonRelease),
+    KeyPress(action::KeyPress),
+    MotionNotify(action::MotionNotify),
+    EnterNotify(action::EnterNotify),
+    LeaveNotify(action::LeaveNotify),
+    DestroyNotify(action::DestroyNotify),
+    ClientMessageRequest(action::ClientMessageRequest),
+    UnknownEvent(action::UnknownEvent),
+    Focus(action::Focus),
+    UpdateLayout(action::UpdateLayout),
+    Destroy(action::Destroy),
+    Kill(action::Kill),
+    PingFocused(action::PingFocused),
+    PingTimeout(action::PingTimeout),
+    Restore(action::Restore),
+    RestoreSession(action::RestoreSession),
+}
+
+impl Dispatched {
+    pub fn dispatch<W, R>(self, store: &mut Store<State<W>, R>)
+    where
+        W: WindowSystem,
+        R: Reactor<State<W>, Output = ()>,
+    {
+        match self {
+            Self::ConfigurationRequest(action) => store.dispatch(action),
+            Self::MapRequest(action) => store.dispatch(action),
+            Self::UnmapNotify(action) => store.dispatch(action),
+            Self::ButtonPress(action) => store.dispatch(action),
+            Self::ButtonRelease(action) => store.dispatch(action),
+            Self::KeyPress(action) => store.dispatch(action),
+            Self::MotionNotify(action) => store.dispatch(action),
+            Self::EnterNotify(action) => store.dispatch(action),
+            Self::LeaveNotify(action) => store.dispatch(action),
+            Self::DestroyNotify(action) => store.dispatch(action),
+            Self::ClientMessageRequest(action) => store.dispatch(action),
+            Self::UnknownEvent(action) => store.dispatch(action),
+            Self::Focus(action) => store.dispatch(action),
+            Self::UpdateLayout(action) => store.dispatch(action),
+            Self::Destroy(action) => store.dispatch(action),
+            Self::Kill(action) => store.dispatch(action),
+            Self::PingFocused(action) => store.dispatch(action),
+            Self::PingTimeout(action) => store.dispatch(action),
+            Self::Restore(action) => store.dispatch(action),
+            Self::RestoreSession(action) => store.dispatch(action),
+        }
+    }
+}
+
+/* First line of a recording, replay builds its monitors from it */
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    screens: Vec<Screen>,
+}
+
+/* Every other line, time is in milliseconds since the recording started */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub time: u64,
+    pub action: Dispatched,
+}
+
+/*
+ * Writes one JSON line per dispatched action. Lines go straight to the file
+ * so a recording survives the abort of a panicking release build
+ */
+pub struct Recorder {
+    file: File,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, screens: Vec<Screen>) -> HadlockResult<Self> {
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", serde_json::to_string(&Header { screens })?)?;
+        Ok(Self {
+            file,
+            start: Instant::now(),
+        })
+    }
+
+    /* A Recorder for the path in HADLOCK_RECORD, None if it isn't set */
+    pub fn from_env(screens: Vec<Screen>) -> Option<Self> {
+        let path = env::var_os(RECORD_ENV)?;
+        match Self::create(Path::new(&path), screens) {
+            Ok(recorder) => {
+                info!("Recording to {:?}", path);
+                Some(recorder)
+            }
+            Err(e) => {
+                error!("Failed to record to {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    pub fn record(&mut self, action: &Dispatched) {
+        let entry = Entry {
+            time: self.start.elapsed().as_millis() as u64,
+            action: action.clone(),
+        };
+        let res = serde_json::to_string(&entry)
+            .map_err(io::Error::from)
+            .and_then(|line| writeln!(self.file, "{}", line));
+        if let Err(e) = res {
+            warn!("Failed to record {:?}: {}", entry.action, e);
+        }
+    }
+}
+
+/* Replays the recording at path on the fake backend, printing State after each step */
+pub fn replay(path: &Path) -> HadlockResult<()> {
+    play(BufReader::new(File::open(path)?), |step, entry, state| {
+        println!("#{} at {}ms: {:?}", step, entry.time, entry.action);
+        println!("{:#?}", state);
+    })
+}
+
+/*
+ * Feeds a recording into a Store on the fake backend. The fake only knows what
+ * the actions tell it, so every window it hasn't seen is added as a normal
+ * client when it is mapped or configured. Internal actions queued by the
+ * reducers are dropped, the recording already holds the ones that ran
+ */
+pub fn play<R, F>(reader: R, mut on_step: F) -> HadlockResult<()>
+where
+    R: BufRead,
+    F: FnMut(usize, &Entry, &State<FakeWindowSystem>),
+{
+    let mut lines = reader.lines();
+    let header: Header = serde_json::from_str(&lines.next().ok_or("Empty recording")??)?;
+    let lib = Rc::new(FakeWindowSystem::new(header.screens));
+    let (tx, rx) = internal_action::channel()?;
+    let mut store = Store::new(
+        State::new(lib.clone(), tx.clone()),
+        HdlReactor::new(lib.clone(), tx),
+    );
+
+    for (i, line) in lines.enumerate() {
+        let entry: Entry = serde_json::from_str(&line?)?;
+        add_window(&lib, &entry.action);
+        entry.action.clone().dispatch(&mut store);
+        let _ = rx.drain();
+        on_step(i + 1, &entry, &store);
+    }
+    Ok(())
+}
+
+fn add_window(lib: &FakeWindowSystem, action: &Dispatched) {
+    let windows = match action {
+        Dispatched::MapRequest(action) => vec![(action.win, FakeWindow::default().geometry)],
+        Dispatched::ConfigurationRequest(action) => vec![(
+            action.win,
+            Geometry {
+                x: action.win_changes.x,
+                y: action.win_changes.y,
+                width: action.win_changes.width as u32,
+                height: action.win_changes.height as u32,
+            },
+        )],
+        Dispatched::RestoreSession(action) => action
+            .session
+            .monitors
+            .iter()
+            .flat_map(|mon| mon.workspaces.iter())
+            .flat_map(|ws| ws.clients.iter())
+            .map(|client| {
+                let rect = client.window_rect;
+                let (pos, size) = (rect.get_position(), rect.get_size());
+                (
+                    client.window,
+                    Geometry {
+                        x: pos.x,
+                        y: pos.y,
+                        width: size.width as u32,
+                        height: size.height as u32,
+                    },
+                )
+            })
+            .collect(),
+        _ => return,
+    };
+    for (w, geometry) in windows {
+        if w == lib.get_root() || lib.window(w).is_some() {
+            continue;
+        }
+        let window = FakeWindow {
+            geometry,
+            ..FakeWindow::default()
+        };
+        lib.insert_window(w, window);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn recorded_session_replays() {
+        let path = env::temp_dir().join(format!("hadlock-recording-{}.jsonl", std::process::id()));
+        let mut recorder =
+            Recorder::create(&path, vec![Screen::new(7, 1000, 500, 0, 0)]).expect("recorder");
+        for win in [10, 11].iter() {
+            recorder.record(&Dispatched::MapRequest(action::MapRequest {
+                win: *win,
+                parent: 7,
+            }));
+        }
+        recorder.record(&Dispatched::Focus(action::Focus { win: 11 }));
+
+        let mut steps = vec![];
+        let file = BufReader::new(File::open(&path).expect("recording"));
+        play(file, |step, _, state| {
+            let mon = state.monitors.get(&0).expect("monitor");
+            steps.push((step, mon.get_client_keys().len(), state.focus_w));
+        })
+        .expect("replay");
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(steps, vec![(1, 1, 7), (2, 2, 7), (3, 2, 11)]);
+    }
+}
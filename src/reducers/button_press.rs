@@ -2,10 +2,11 @@
 use {
     crate::{
         config::CONFIG,
-        models::{rect::*, window_type::WindowType, windowwrapper::*},
+        models::{rect::*, window_type::WindowType, windowwrapper::*, Corner},
         state::State,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::masks::*,
         xlibwrapper::util::*,
@@ -15,17 +16,35 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::ButtonPress> for State {
+impl<W: WindowSystem> Reducer<action::ButtonPress> for State<W> {
     fn reduce(&mut self, action: action::ButtonPress) {
         //debug!("ButtonPress");
+        if self.drag_client.is_some() {
+            // A click confirms a keyboard move/resize
+            wm::end_client_drag(self);
+            return;
+        }
         let geometry = self.lib.get_geometry(action.win);
         self.drag_start_pos = (action.x_root as i32, action.y_root as i32);
         self.drag_start_frame_pos = (geometry.x, geometry.y);
         self.drag_start_frame_size = (geometry.width, geometry.height);
+        self.drag_corner = None;
 
         if action.button == Button1 && (action.state & Mod4Mask) == Mod4Mask {
             //debug!("should raise");
-            self.lib.raise_window(action.win);
+            wm::raise_window(self, action.win);
+            self.lib.set_move_cursor();
+        }
+
+        if action.button == Button3 && (action.state & Mod4Mask) == Mod4Mask {
+            let corner = Corner::nearest(
+                &Rect::from(geometry),
+                action.x_root as i32,
+                action.y_root as i32,
+            );
+            self.drag_corner = Some(corner);
+            wm::raise_window(self, action.win);
+            self.lib.set_resize_cursor(corner);
         }
     }
 }
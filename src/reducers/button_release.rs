@@ -2,9 +2,13 @@
 use {
     crate::{
         config::CONFIG,
-        models::{rect::*, window_type::WindowType, windowwrapper::*},
+        layout::LayoutTag,
+        models::{rect::*, window_type::WindowType, windowwrapper::*, WindowState, HandleState},
+        error::{self, HadlockError},
         state::State,
+        wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::masks::*,
         xlibwrapper::util::*,
@@ -14,8 +18,95 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::ButtonRelease> for State {
-    fn reduce(&mut self, _action: action::ButtonRelease) {
+impl<W: WindowSystem> Reducer<action::ButtonRelease> for State<W> {
+    fn reduce(&mut self, action: action::ButtonRelease) {
         debug!("ButtonRelease");
+        let action = match self.drag_client {
+            Some(_) if self.drag_keyboard => return,
+            Some(w) => {
+                let button = if self.drag_corner.is_some() { Button3 } else { Button1 };
+                wm::end_client_drag(self);
+                action::ButtonRelease {
+                    win: w,
+                    sub_win: w,
+                    button,
+                    ..action
+                }
+            }
+            None => action,
+        };
+        self.drag_corner = None;
+        if action.button == Button1 {
+            drop_tiled(self, &action);
+        }
+
+        let zone = match self.drag_snap_zone.take() {
+            Some(zone) if action.button == Button1 => zone,
+            _ => return,
+        };
+
+        let mon = match wm::get_mon_by_window(self, action.win) {
+            Some(mon_id) => match self.monitors.get_mut(&mon_id) {
+                Some(mon) => mon,
+                None => return error::report("ButtonRelease", Err(HadlockError::NoMonitor(mon_id))),
+            },
+            None => return,
+        };
+        if mon.get_current_layout() != Some(LayoutTag::Floating) {
+            return;
+        }
+
+        let ww = match mon.get_client(action.win) {
+            Some(ww) if ww.current_state != WindowState::Monocle => ww.clone(),
+            _ => return,
+        };
+        let (pos, size) = mon.snap(&ww, zone);
+        mon.swap_window(action.win, |_, ww| WindowWrapper {
+            window_rect: Rect::new(pos, size),
+            restore_position: ww.get_position(),
+            restore_size: ww.get_size(),
+            previous_state: ww.current_state,
+            current_state: WindowState::Snapped,
+            snap_zone: Some(zone),
+            handle_state: vec![HandleState::Move, HandleState::Resize].into(),
+            ..ww
+        });
+    }
+}
+
+/*
+ * Ends a Mod+Button1 drag of a tiled window: the window is moved to the monitor under
+ * the pointer and swapped with the drop target, if any
+ */
+fn drop_tiled<W: WindowSystem>(state: &mut State<W>, action: &action::ButtonRelease) -> Option<()> {
+    let target = state.drag_target.take();
+    let src_mon = wm::get_mon_by_window(state, action.win)?;
+    let src = state.monitors.get(&src_mon)?;
+    if src.get_current_layout()? == LayoutTag::Floating || src.get_client(action.win)?.is_trans {
+        return None;
+    }
+
+    let dest_mon = wm::get_monitor_by_point(state, action.x_root as i32, action.y_root as i32);
+    if target.is_none() && dest_mon == src_mon {
+        return None;
+    }
+
+    if dest_mon != src_mon {
+        let ws = state.monitors.get(&dest_mon)?.current_ws;
+        state.current_monitor = src_mon;
+        wm::move_to_ws(state, action.win, ws);
+        wm::reorder_monitor(state, src_mon);
+        state.current_monitor = dest_mon;
+    }
+
+    if let Some(target) = target {
+        wm::swap_toc(state.monitors.get_mut(&dest_mon)?, action.win, target);
+    }
+    if state.monitors.get(&dest_mon)?.get_current_layout()? != LayoutTag::Floating {
+        wm::reorder_monitor(state, dest_mon);
+    }
+    if let Some(target) = target {
+        wm::push_handle_state(state, target, HandleState::DropTargetClear);
     }
+    Some(())
 }
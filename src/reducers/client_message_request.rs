@@ -2,12 +2,16 @@
 use {
     crate::{
         config::CONFIG,
+        layout::LayoutTag,
         models::{
-            monitor::Monitor, rect::*, window_type::WindowType, windowwrapper::*, HandleState,
+            internal_action::InternalAction, monitor::Monitor, rect::*, window_type::WindowType,
+            windowwrapper::*, Corner, HandleState, Layer, WindowState,
         },
+        error::{self, HadlockError},
         state::State,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -17,68 +21,323 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::ClientMessageRequest> for State {
-    // Full credit for this solution goes to lex148
+impl<W: WindowSystem> Reducer<action::ClientMessageRequest> for State<W> {
     fn reduce(&mut self, action: action::ClientMessageRequest) {
+        error::report("ClientMessageRequest", client_message_request(self, action));
+    }
+}
+
+// Full credit for this solution goes to lex148
+fn client_message_request<W: WindowSystem>(
+    state: &mut State<W>,
+    action: action::ClientMessageRequest,
+) -> Result<(), HadlockError> {
+    let name = state.lib.xatom().get_name(action.message_type);
+
+    debug!("client message: {}", name);
+
+    let data_zero = *action
+        .data
+        .get(0)
+        .ok_or(HadlockError::MalformedMessage(action.message_type))?;
+    debug!("data_zero: {:?}", data_zero);
+    let data_one = *action
+        .data
+        .get(1)
+        .ok_or(HadlockError::MalformedMessage(action.message_type))?;
+    debug!("data_one: {:?}", data_one);
+    let data_two = *action
+        .data
+        .get(2)
+        .ok_or(HadlockError::MalformedMessage(action.message_type))?;
+    debug!("data_two: {:?}", data_two);
+    
+    if action.message_type == state.lib.xatom().NetCurrentDesktop {
+        wm::set_current_ws(state, data_zero as u32);
+    }
+
+    if action.message_type == state.lib.xatom().NetActiveWindow {
+        activate_window(state, action.win);
+    }
+
+    if action.message_type == state.lib.xatom().NetCloseWindow {
+        wm::close_window(state, action.win);
+    }
+
+    if action.message_type == state.lib.xatom().WMProtocols
+        && data_zero == state.lib.xatom().NetWMPing as i64
+    {
+        wm::pong(state, data_two as Window, data_one);
+    }
 
-        let name = self.lib.xatom.get_name(action.message_type);
-
-        debug!("client message: {}", name);
-
-        let data_zero = *action
-            .data
-            .get(0)
-            .expect("client_message_request: cleanupt");
-        debug!("data_zero: {:?}", data_zero);
-        let data_one = *action
-            .data
-            .get(1)
-            .expect("client_message_request: cleanupt");
-        debug!("data_one: {:?}", data_one);
-        let data_two = *action
-            .data
-            .get(2)
-            .expect("client_message_request: cleanupt");
-        debug!("data_two: {:?}", data_two);
-        
-        if action.message_type == self.lib.xatom.NetCurrentDesktop {
-            wm::set_current_ws(self, data_zero as u32);
+    if action.message_type == state.lib.xatom().NetWMDesktop {
+        if data_zero as u32 == ALL_DESKTOPS {
+            wm::set_sticky(state, action.win, true);
+        } else {
+            move_to_desktop(state, action.win, data_zero as u32);
         }
+    }
 
+    if action.message_type == state.lib.xatom().NetMoveResizeWindow {
+        move_resize_window(state, action.win, &action.data);
+    }
 
-        if action.message_type == self.lib.xatom.NetWMState
-            && (data_one == self.lib.xatom.NetWMStateFullscreen as i64
-                || data_two == self.lib.xatom.NetWMStateFullscreen as i64)
-        {
-            //debug!("Actually fullscreen");
-            let set_fullscreen = data_zero == 1;
-            let toggle_fullscreen = data_zero == 2;
-
-            let mut states = self.lib.get_window_states_atoms(action.win);
-
-            //determine what to change the state to
-            let fullscreen = if toggle_fullscreen {
-                !states.contains(&self.lib.xatom.NetWMStateFullscreen)
-            } else {
-                set_fullscreen
-            };
-
-            //update the list of states
-            if fullscreen {
-                states.push(self.lib.xatom.NetWMStateFullscreen);
-            } else {
-                states.retain(|x| x != &self.lib.xatom.NetWMStateFullscreen);
+    if action.message_type == state.lib.xatom().NetWMMoveResize {
+        start_client_drag(state, action.win, &action.data);
+    }
+
+
+    if action.message_type == state.lib.xatom().NetWMState {
+        for (atom, layer) in [
+            (state.lib.xatom().NetWMStateAbove, Layer::Above),
+            (state.lib.xatom().NetWMStateBelow, Layer::Below),
+        ] {
+            if data_one == atom as i64 || data_two == atom as i64 {
+                change_layer(state, action.win, layer, data_zero);
             }
-            states.sort();
-            states.dedup();
-
-            //set the windows state
-            self.lib.set_window_states_atoms(action.win, states);
-            let mon = self
-                .monitors
-                .get_mut(&self.current_monitor)
-                .expect("ClientMessageRequest - monitor - get_mut");
-            mon.swap_window(action.win, |mon, ww| wm::toggle_monocle(mon, ww));
         }
+        let sticky = state.lib.xatom().NetWMStateSticky as i64;
+        if data_one == sticky || data_two == sticky {
+            change_sticky(state, action.win, data_zero);
+        }
+        let hidden = state.lib.xatom().NetWMStateHidden as i64;
+        if data_one == hidden || data_two == hidden {
+            change_hidden(state, action.win, data_zero);
+        }
+    }
+
+    if action.message_type == state.lib.xatom().WMChangeState
+        && data_zero == ICONIC_STATE as i64
+    {
+        wm::minimize(state, action.win);
+    }
+
+    if action.message_type == state.lib.xatom().NetWMState
+        && (data_one == state.lib.xatom().NetWMStateFullscreen as i64
+            || data_two == state.lib.xatom().NetWMStateFullscreen as i64)
+    {
+        //debug!("Actually fullscreen");
+        let set_fullscreen = data_zero == 1;
+        let toggle_fullscreen = data_zero == 2;
+
+        let mut states = state.lib.get_window_states_atoms(action.win);
+
+        //determine what to change the state to
+        let fullscreen = if toggle_fullscreen {
+            !states.contains(&state.lib.xatom().NetWMStateFullscreen)
+        } else {
+            set_fullscreen
+        };
+
+        //update the list of states
+        if fullscreen {
+            states.push(state.lib.xatom().NetWMStateFullscreen);
+        } else {
+            states.retain(|x| x != &state.lib.xatom().NetWMStateFullscreen);
+        }
+        states.sort();
+        states.dedup();
+
+        //set the windows state
+        state.lib.set_window_states_atoms(action.win, states);
+        let mon = state.monitor_mut(state.current_monitor)?;
+        mon.swap_window(action.win, |mon, ww| wm::toggle_monocle(mon, ww));
+    }
+    Ok(())
+}
+
+/* Switches to the workspace of w, wherever it is, and focuses it */
+fn activate_window<W: WindowSystem>(state: &mut State<W>, w: Window) -> Option<()> {
+    if state.minimized.contains(&w) {
+        return wm::restore(state, w);
+    }
+    let mon_id = wm::get_mon_by_window(state, w)?;
+    let ws = wm::get_ws_by_window(state, w)?;
+    if mon_id != state.current_monitor || ws != state.monitors.get(&mon_id)?.current_ws {
+        wm::set_current_ws(state, ws)?;
+    }
+    let _ = state.tx.send(InternalAction::FocusSpecific(w));
+    Some(())
+}
+
+/*
+ * Moves w from whatever workspace it is on to desktop, move_to_ws works on the
+ * current workspace of the current monitor so that is pointed at w for the move
+ */
+fn move_to_desktop<W: WindowSystem>(state: &mut State<W>, w: Window, desktop: u32) -> Option<()> {
+    let mon_id = wm::get_mon_by_window(state, w)?;
+    let ws = wm::get_ws_by_window(state, w)?;
+    if ws == desktop || desktop >= CONFIG.workspaces.len() as u32 {
+        return Some(());
+    }
+
+    let prev_mon = state.current_monitor;
+    let prev_ws = state.monitors.get(&mon_id)?.current_ws;
+    state.current_monitor = mon_id;
+    state.monitors.get_mut(&mon_id)?.current_ws = ws;
+    wm::move_to_ws(state, w, desktop);
+    state.monitors.get_mut(&mon_id)?.current_ws = prev_ws;
+    state.current_monitor = prev_mon;
+
+    let dest_mon = wm::get_mon_by_ws(state, desktop);
+    for id in [Some(mon_id), dest_mon].iter().flatten() {
+        if state.monitors.get(id)?.get_current_layout()? != LayoutTag::Floating {
+            wm::reorder_monitor(state, *id);
+        }
+    }
+    Some(())
+}
+
+/*
+ * Programmatic geometry is only honored where windows keep their own rect,
+ * bits 8-11 of data[0] tell which of x, y, width and height are set
+ */
+fn move_resize_window<W: WindowSystem>(
+    state: &mut State<W>,
+    w: Window,
+    data: &[i64],
+) -> Option<()> {
+    let flags = *data.first()?;
+    let mon = state.monitors.get_mut(&wm::get_mon_by_window(state, w)?)?;
+    let layout = mon.get_current_layout()?;
+    let ww = mon.get_client_mut(w)?;
+    if layout != LayoutTag::Floating && !ww.floats() && !ww.is_trans {
+        return Some(());
+    }
+
+    let (mut pos, mut size) = (ww.get_position(), ww.get_size());
+    if flags & (1 << 8) != 0 {
+        pos.x = *data.get(1)? as i32;
+    }
+    if flags & (1 << 9) != 0 {
+        pos.y = *data.get(2)? as i32;
+    }
+    if flags & (1 << 10) != 0 {
+        size.width = *data.get(3)? as i32;
+    }
+    if flags & (1 << 11) != 0 {
+        size.height = *data.get(4)? as i32;
+    }
+    ww.set_position(pos);
+    ww.set_size(size);
+    ww.snap_zone = None;
+    ww.handle_state = vec![HandleState::Move, HandleState::Resize].into();
+    Some(())
+}
+
+const MOVERESIZE_SIZE_KEYBOARD: i64 = 9;
+const MOVERESIZE_MOVE_KEYBOARD: i64 = 10;
+const MOVERESIZE_CANCEL: i64 = 11;
+
+/*
+ * Starts the same drag as Mod+Button1/Button3 for a window that asked for it,
+ * data is x_root, y_root and the direction of the move/resize
+ */
+fn start_client_drag<W: WindowSystem>(state: &mut State<W>, w: Window, data: &[i64]) -> Option<()> {
+    let direction = *data.get(2)?;
+    if direction == MOVERESIZE_CANCEL {
+        if state.drag_client == Some(w) {
+            wm::end_client_drag(state);
+        }
+        return Some(());
+    }
+    if state.drag_client.is_some() {
+        return None;
+    }
+
+    let mon = state.monitors.get(&wm::get_mon_by_window(state, w)?)?;
+    let floating = mon.get_current_layout()? == LayoutTag::Floating;
+    let ww = mon.get_client(w)?;
+    if ww.current_state == WindowState::Monocle {
+        return None;
+    }
+    let keyboard = direction == MOVERESIZE_SIZE_KEYBOARD || direction == MOVERESIZE_MOVE_KEYBOARD;
+    if keyboard && !floating && !ww.floats() && !ww.is_trans {
+        return None;
+    }
+
+    let corner = match direction {
+        0 => Some(Corner::NorthWest),
+        1 => Some(Corner::North),
+        2 => Some(Corner::NorthEast),
+        3 => Some(Corner::East),
+        4 | MOVERESIZE_SIZE_KEYBOARD => Some(Corner::SouthEast),
+        5 => Some(Corner::South),
+        6 => Some(Corner::SouthWest),
+        7 => Some(Corner::West),
+        8 | MOVERESIZE_MOVE_KEYBOARD => None,
+        _ => return None,
+    };
+
+    if !state.lib.grab_pointer(corner) {
+        return None;
+    }
+    if keyboard && !state.lib.grab_keyboard() {
+        state.lib.ungrab_pointer();
+        return None;
+    }
+
+    let geometry = state.lib.get_geometry(w);
+    state.drag_start_pos = (*data.first()? as i32, *data.get(1)? as i32);
+    state.drag_start_frame_pos = (geometry.x, geometry.y);
+    state.drag_start_frame_size = (geometry.width, geometry.height);
+    state.drag_corner = corner;
+    state.drag_client = Some(w);
+    state.drag_keyboard = keyboard;
+    wm::raise_window(state, w);
+    Some(())
+}
+
+/* _NET_WM_STATE_ABOVE/BELOW, action is remove (0), add (1) or toggle (2) */
+fn change_layer<W: WindowSystem>(
+    state: &mut State<W>,
+    w: Window,
+    layer: Layer,
+    action: i64,
+) -> Option<()> {
+    let mon = state.monitors.get_mut(&wm::get_mon_by_window(state, w)?)?;
+    let ww = mon
+        .workspaces
+        .values_mut()
+        .find_map(|ws| ws.clients.get_mut(&w))?;
+    let set = match action {
+        0 => false,
+        1 => true,
+        2 => ww.layer != layer,
+        _ => return None,
+    };
+    if set {
+        ww.layer = layer;
+    } else if ww.layer == layer {
+        ww.layer = Layer::Normal;
+    }
+    ww.handle_state.borrow_mut().push(HandleState::Restack);
+    Some(())
+}
+
+/* _NET_WM_STATE_STICKY, action is remove (0), add (1) or toggle (2) */
+fn change_sticky<W: WindowSystem>(state: &mut State<W>, w: Window, action: i64) -> Option<()> {
+    let mon = state.monitors.get(&wm::get_mon_by_window(state, w)?)?;
+    let sticky = match action {
+        0 => false,
+        1 => true,
+        2 => !mon.get_client(w)?.is_sticky,
+        _ => return None,
+    };
+    wm::set_sticky(state, w, sticky)
+}
+
+/* _NET_WM_STATE_HIDDEN, action is remove (0), add (1) or toggle (2) */
+fn change_hidden<W: WindowSystem>(state: &mut State<W>, w: Window, action: i64) -> Option<()> {
+    let hidden = match action {
+        0 => false,
+        1 => true,
+        2 => !state.minimized.contains(&w),
+        _ => return None,
+    };
+    if hidden {
+        wm::minimize(state, w)
+    } else {
+        wm::restore(state, w)
     }
 }
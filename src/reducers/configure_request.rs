@@ -5,9 +5,11 @@ use {
         models::{
             monitor::Monitor, rect::*, window_type::WindowType, windowwrapper::*, HandleState,
         },
+        error::{self, HadlockError},
         state::State,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -17,35 +19,70 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::ConfigurationRequest> for State {
+impl<W: WindowSystem> Reducer<action::ConfigurationRequest> for State<W> {
     fn reduce(&mut self, action: action::ConfigurationRequest) {
-        //debug!("ConfigurationRequest for window: {} - {:?}", action.win, action.win_changes);
-        let mon = self
-            .monitors
-            .get_mut(&self.current_monitor)
-            .expect("ConfigurationRequest - monitor - get_mut");
+        error::report("ConfigurationRequest", configure_request(self, action));
+    }
+}
+
+fn configure_request<W: WindowSystem>(
+    state: &mut State<W>,
+    action: action::ConfigurationRequest,
+) -> Result<(), HadlockError> {
+    //debug!("ConfigurationRequest for window: {} - {:?}", action.win, action.win_changes);
+    let mon_id = wm::get_mon_by_window(state, action.win).unwrap_or(state.current_monitor);
+    let mon = state.monitor_mut(mon_id)?;
+
+    if mon.get_client(action.win).is_some_and(|ww| ww.floats()) {
+        // Floating windows keep their own rect, so they get what they ask for
+        let ww = mon
+            .get_client_mut(action.win)
+            .ok_or(HadlockError::NoClient(action.win))?;
+        let (mut pos, mut size) = (ww.get_position(), ww.get_size());
+        let mask = action.value_mask;
+        if mask & xlib::CWX as u64 != 0 {
+            pos.x = action.win_changes.x;
+        }
+        if mask & xlib::CWY as u64 != 0 {
+            pos.y = action.win_changes.y;
+        }
+        if mask & xlib::CWWidth as u64 != 0 {
+            size.width = action.win_changes.width;
+        }
+        if mask & xlib::CWHeight as u64 != 0 {
+            size.height = action.win_changes.height;
+        }
+        ww.set_position(pos);
+        ww.set_size(size);
+        state.lib.configure_window(action.win, action.value_mask as i64, action.win_changes);
+        return Ok(());
+    }
 
-        if mon.contains_window(action.win) {
-            if action.value_mask & (xlib::CWX | xlib::CWY) as u64 == (xlib::CWX | xlib::CWY) as u64
-            {
-                return;
-            }
-            let ww = mon.remove_window(action.win).expect("ConfigurationRequest - monitor - remove_window");
-            self.lib.configure_window(
-                action.win,
-                action.value_mask as i64,
-                WindowChanges {
-                    x: ww.window_rect.get_position().x,
-                    y: ww.window_rect.get_position().y,
-                    width: ww.window_rect.get_size().width,
-                    height: ww.window_rect.get_size().height,
-                    ..action.win_changes
-                },
-            );
-            mon.add_window(ww.window(), ww);
-        } else {
-            self.lib
-                .configure_window(action.win, action.value_mask as i64, action.win_changes);
+    if mon.contains_window(action.win) {
+        if action.value_mask & (xlib::CWX | xlib::CWY) as u64 == (xlib::CWX | xlib::CWY) as u64
+        {
+            return Ok(());
         }
+        // Managed windows keep the rect hadlock gave them, wherever their workspace is
+        let (pos, size) = mon
+            .workspaces
+            .values()
+            .find_map(|ws| ws.clients.get(&action.win))
+            .map(|ww| (ww.get_position(), ww.get_size()))
+            .ok_or(HadlockError::NoClient(action.win))?;
+        state.lib.configure_window(
+            action.win,
+            action.value_mask as i64,
+            WindowChanges {
+                x: pos.x,
+                y: pos.y,
+                width: size.width,
+                height: size.height,
+                ..action.win_changes
+            },
+        );
+    } else {
+        state.lib.configure_window(action.win, action.value_mask as i64, action.win_changes);
     }
+    Ok(())
 }
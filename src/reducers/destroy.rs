@@ -3,8 +3,11 @@ use {
     crate::{
         config::CONFIG,
         models::{rect::*, window_type::WindowType, windowwrapper::*},
+        error::{self, HadlockError},
         state::State,
+        wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -14,19 +17,37 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::Destroy> for State {
+impl<W: WindowSystem> Reducer<action::Destroy> for State<W> {
     fn reduce(&mut self, action: action::Destroy) {
-        //debug!("DestroyNotify");
-        if action.win == self.lib.get_root() {
-            return;
-        }
+        error::report("Destroy", destroy(self, action));
+    }
+}
+
+fn destroy<W: WindowSystem>(
+    state: &mut State<W>,
+    action: action::Destroy,
+) -> Result<(), HadlockError> {
+    //debug!("DestroyNotify");
+    if action.win == state.lib.get_root() {
+        return Ok(());
+    }
+
+    state.stack.borrow_mut().retain(|w| *w != action.win);
+    state.dock_windows.retain(|w| *w != action.win);
+    state.minimized.retain(|w| *w != action.win);
+    state.pings.remove(&action.win);
+    state.closes.remove(&action.win);
+    state.unmaps.borrow_mut().remove(&action.win);
 
-        let mon = self
-            .monitors
-            .get_mut(&self.current_monitor)
-            .expect("DestroyNotify - get_mut");
-        if mon.contains_window(action.win) {
-            mon.remove_window(action.win);
+    // The window can be on any monitor and workspace, not just the current one
+    let mon_id = match wm::get_mon_by_window(state, action.win) {
+        Some(mon_id) => mon_id,
+        None => return Ok(()),
+    };
+    for ws in state.monitor_mut(mon_id)?.workspaces.values_mut() {
+        if ws.remove_window(action.win).is_some() {
+            ws.proportions.weights.remove(&action.win);
         }
     }
+    Ok(())
 }
@@ -0,0 +1,44 @@
+#![allow(unused_imports)]
+use {
+    crate::{
+        layout::LayoutTag,
+        models::internal_action::InternalAction,
+        state::State,
+        wm,
+        xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
+    },
+    reducer::*,
+};
+
+/* A client that closed itself, e.g. after WM_DELETE_WINDOW, leaves a gap to close */
+impl<W: WindowSystem> Reducer<action::DestroyNotify> for State<W> {
+    fn reduce(&mut self, action: action::DestroyNotify) {
+        // Docks and notifications aren't on any monitor, they are only known here
+        self.stack.borrow_mut().retain(|w| *w != action.win);
+        self.dock_windows.retain(|w| *w != action.win);
+        let mon_id = match wm::get_mon_by_window(self, action.win) {
+            Some(mon_id) => mon_id,
+            None => return,
+        };
+        self.reduce(action::Destroy { win: action.win });
+        let clients = self
+            .monitors
+            .values()
+            .flat_map(|mon| mon.get_client_keys())
+            .collect();
+        self.lib.update_net_client_list(clients);
+
+        let tiled = self
+            .monitors
+            .get(&mon_id)
+            .and_then(|mon| mon.get_current_layout())
+            .is_some_and(|layout| layout != LayoutTag::Floating);
+        if tiled {
+            wm::reorder_monitor(self, mon_id);
+        }
+        if self.focus_w == action.win {
+            let _ = self.tx.send(InternalAction::Focus);
+        }
+    }
+}
@@ -3,10 +3,12 @@ use {
     crate::{
         config::CONFIG,
         models::{rect::*, window_type::WindowType, windowwrapper::*, HandleState},
+        error::{self, HadlockError},
         state::State,
         layout::*,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -16,42 +18,48 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::EnterNotify> for State {
+impl<W: WindowSystem> Reducer<action::EnterNotify> for State<W> {
     fn reduce(&mut self, action: action::EnterNotify) {
-        //debug!("EnterNotify");
-        let window_mon = wm::get_mon_by_window(&self, action.win);
-        if let Some(mon_id) = window_mon {
-            if mon_id != self.current_monitor {
-                self.current_monitor = mon_id;
-                self.monitors
-                    .get_mut(&self.current_monitor)
-                    .unwrap()
-                    .handle_state
-                    .replace(HandleState::Focus);
-            }
-        }
-        
-        let mon = self
-            .monitors
-            .get_mut(&self.current_monitor)
-            .expect("EnterNotify - monitor - get_mut");
-        
-        if action.win == self.lib.get_root() && mon.get_current_layout() != Some(LayoutTag::Floating) {
-            return
-        }
-        
-        if let Some(w) = mon.get_client_mut(self.focus_w) {
-            w.handle_state = HandleState::Unfocus.into();
-        }
+        error::report("EnterNotify", enter_notify(self, action));
+    }
+}
 
-        self.focus_w = action.win;
-        if let Some(w) = self
-            .monitors
-            .get_mut(&self.current_monitor)
-            .expect("EnterNotify - monitor - get_mut")
-            .get_client_mut(action.win)
-        {
-            w.handle_state = HandleState::Focus.into();
+fn enter_notify<W: WindowSystem>(
+    state: &mut State<W>,
+    action: action::EnterNotify,
+) -> Result<(), HadlockError> {
+    //debug!("EnterNotify");
+    let window_mon = wm::get_mon_by_window(state, action.win);
+    if let Some(mon_id) = window_mon {
+        if mon_id != state.current_monitor {
+            state.current_monitor = mon_id;
+            state
+                .monitor(mon_id)?
+                .handle_state
+                .replace(HandleState::Focus);
         }
     }
+    
+    let mon_id = state.current_monitor;
+    let mon = state
+        .monitors
+        .get_mut(&mon_id)
+        .ok_or(HadlockError::NoMonitor(mon_id))?;
+    
+    if action.win == state.lib.get_root() && mon.get_current_layout() != Some(LayoutTag::Floating) {
+        return Ok(());
+    }
+    
+    if let Some(w) = mon.get_client_mut(state.focus_w) {
+        w.handle_state = HandleState::Unfocus.into();
+    }
+
+    state.focus_w = action.win;
+    if let Some(w) = state
+        .monitor_mut(state.current_monitor)?
+        .get_client_mut(action.win)
+    {
+        w.handle_state = HandleState::Focus.into();
+    }
+    Ok(())
 }
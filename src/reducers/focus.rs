@@ -5,9 +5,11 @@ use {
         models::{
             monitor::Monitor, rect::*, window_type::WindowType, windowwrapper::*, HandleState,
         },
+        error::{self, HadlockError},
         state::State,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -17,51 +19,60 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::Focus> for State {
+impl<W: WindowSystem> Reducer<action::Focus> for State<W> {
     fn reduce(&mut self, action: action::Focus) {
-        if action.win == self.lib.get_root() {
-            return;
-        }
+        error::report("Focus", focus(self, action));
+    }
+}
 
-        match wm::get_mon_by_window(self, action.win) {
-            Some(_mon) => {
-                //let (_class, _something) = self.lib.get_class_hint(action.win);
-                /*debug!(
-                    "Sending clients top window is not root. Win ({},{}) is in mon {}",
-                    class, something, mon
-                );*/
-                let curr_mon = self
-                    .monitors
-                    .get_mut(&self.current_monitor)
-                    .expect("Focus - get_monitor");
+fn focus<W: WindowSystem>(
+    state: &mut State<W>,
+    action: action::Focus,
+) -> Result<(), HadlockError> {
+    if action.win == state.lib.get_root() {
+        return Ok(());
+    }
 
-                //unset focus
-                if self.focus_w != self.lib.get_root() {
-                    let mut old_focus = match curr_mon.remove_window(self.focus_w) {
-                        Some(win) => win,
-                        None => return,
-                    };
-                    old_focus = WindowWrapper {
-                        handle_state: HandleState::Unfocus.into(),
-                        ..old_focus
-                    };
-                    curr_mon.add_window(self.focus_w, old_focus);
-                }
+    match wm::get_mon_by_window(state, action.win) {
+        Some(_mon) => {
+            //let (_class, _something) = state.lib.get_class_hint(action.win);
+            /*debug!(
+                "Sending clients top window is not root. Win ({},{}) is in mon {}",
+                class, something, mon
+            );*/
+            let mon_id = state.current_monitor;
+            let curr_mon = state
+                .monitors
+                .get_mut(&mon_id)
+                .ok_or(HadlockError::NoMonitor(mon_id))?;
 
-                //set focus
-                self.focus_w = action.win;
-                let mut new_focus = match curr_mon.remove_window(self.focus_w) {
+            //unset focus
+            if state.focus_w != state.lib.get_root() {
+                let mut old_focus = match curr_mon.remove_window(state.focus_w) {
                     Some(win) => win,
-                    None => return,
+                    None => return Ok(()),
                 };
-
-                new_focus = WindowWrapper {
-                    handle_state: HandleState::Focus.into(),
-                    ..new_focus
+                old_focus = WindowWrapper {
+                    handle_state: HandleState::Unfocus.into(),
+                    ..old_focus
                 };
-                curr_mon.add_window(self.focus_w, new_focus);
+                curr_mon.add_window(state.focus_w, old_focus);
             }
-            None => (),
+
+            //set focus
+            state.focus_w = action.win;
+            let mut new_focus = match curr_mon.remove_window(state.focus_w) {
+                Some(win) => win,
+                None => return Ok(()),
+            };
+
+            new_focus = WindowWrapper {
+                handle_state: HandleState::Focus.into(),
+                ..new_focus
+            };
+            curr_mon.add_window(state.focus_w, new_focus);
         }
+        None => (),
     }
+    Ok(())
 }
@@ -10,6 +10,7 @@ use {
         state::State,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::masks::*,
         xlibwrapper::util::keysym_lookup::*,
@@ -19,12 +20,17 @@ use {
     notify_rust::{Notification, Timeout},
     reducer::*,
     std::cell::RefCell,
-    std::process::Command,
+    std::io::{Read, Write},
+    std::process::{Command, Stdio},
     std::rc::Rc,
 };
 
-impl Reducer<action::KeyPress> for State {
+impl<W: WindowSystem> Reducer<action::KeyPress> for State<W> {
     fn reduce(&mut self, action: action::KeyPress) {
+        if self.drag_keyboard {
+            keyboard_drag(self, action);
+            return;
+        }
         let mod_not_shift = (action.state & (Mod4Mask | Shift)) == Mod4Mask;
         let mod_and_shift = (action.state & (Mod4Mask | Shift)) == Mod4Mask | Shift;
 
@@ -32,11 +38,7 @@ impl Reducer<action::KeyPress> for State {
         debug!("KeyCode to string: {:?}", into_hdl_keysym(&sym));
 
         let ws_keys: Vec<u8> = (1..=9)
-            .map(|x| {
-                self.lib
-                    .str_to_keycode(&x.to_string())
-                    .expect("key_press 1")
-            })
+            .filter_map(|x| self.lib.str_to_keycode(&x.to_string()))
             .collect();
 
         let mon = match self.monitors.get_mut(&self.current_monitor) {
@@ -62,8 +64,8 @@ impl Reducer<action::KeyPress> for State {
     }
 }
 
-fn managed_client(
-    state: &mut State,
+fn managed_client<W: WindowSystem>(
+    state: &mut State<W>,
     action: action::KeyPress,
     mod_not_shift: bool,
     mod_and_shift: bool,
@@ -75,11 +77,9 @@ fn managed_client(
     if mod_not_shift && state.lib.str_to_keycode("Return")? == keycode {
         spawn_process(CONFIG.term.as_str(), vec![]);
     }
-    let resize = state
-        .monitors
-        .get(&state.current_monitor)?
-        .get_current_layout()?
-        == LayoutTag::Floating;
+    let mon = state.monitors.get(&state.current_monitor)?;
+    let resize = mon.get_current_layout()? == LayoutTag::Floating
+        || mon.get_client(state.focus_w)?.floats();
     if mod_and_shift {
         let old_size = state
             .monitors
@@ -143,29 +143,49 @@ fn managed_client(
             }
 
             HDLKeysym::XK_q => {
-                let ww = state
-                    .monitors
-                    .get_mut(&state.current_monitor)?
-                    .get_client_mut(state.focus_w)?;
+                wm::close_window(state, state.focus_w);
+            }
 
-                ww.handle_state.replace(HandleState::Destroy.into());
+            HDLKeysym::XK_x => {
+                wm::push_handle_state(state, state.focus_w, HandleState::Kill);
             }
 
             HDLKeysym::XK_e => {
                 state.lib.exit();
             }
 
+            HDLKeysym::XK_r => {
+                let _ = state.tx.send(internal_action::InternalAction::Restart);
+            }
+
             HDLKeysym::XK_f => {
                 let mon = state.monitors.get_mut(&state.current_monitor)?;
                 mon.swap_window(state.focus_w, |mon, ww| wm::toggle_monocle(mon, ww));
             }
 
+            HDLKeysym::XK_space => {
+                wm::toggle_floating(state, state.focus_w);
+            }
+
+            HDLKeysym::XK_s => {
+                let sticky = state
+                    .monitors
+                    .get(&state.current_monitor)?
+                    .get_client(state.focus_w)?
+                    .is_sticky;
+                wm::set_sticky(state, state.focus_w, !sticky);
+            }
+
             HDLKeysym::XK_l => {
                 debug!("should print layout type");
                 circulate_layout(state);
                 wm::reorder(state);
             }
 
+            HDLKeysym::XK_n => {
+                restore_picker(state);
+            }
+
             _ => {
                 if ws_keys.contains(&keycode) {
                     let ws_num = keycode_to_ws(keycode);
@@ -189,6 +209,9 @@ fn managed_client(
         println!("Number pressed");
 
         match into_hdl_keysym(&state.lib.keycode_to_key_sym(keycode)) {
+            HDLKeysym::XK_n => {
+                wm::minimize(state, state.focus_w);
+            }
             HDLKeysym::XK_f => {
                 let mon = state.monitors.get_mut(&state.current_monitor)?;
                 mon.swap_window(state.focus_w, |mon, ww| wm::toggle_maximize(mon, ww));
@@ -222,6 +245,7 @@ fn managed_client(
                         window_rect: rect,
                         previous_state: ww.current_state,
                         current_state: WindowState::Free,
+                        snap_zone: None,
                         handle_state: HandleState::Center.into(),
                         ..ww
                     });
@@ -232,13 +256,7 @@ fn managed_client(
             }
 
             HDLKeysym::XK_r => {
-                let current_layout = state
-                    .monitors
-                    .get(&state.current_monitor)?
-                    .get_current_layout()?;
-                if current_layout == LayoutTag::Floating {
-                    wm::reorder(state);
-                }
+                reset_layout(state);
             }
 
             _ => {
@@ -253,8 +271,8 @@ fn managed_client(
     Some(())
 }
 
-fn root(
-    state: &mut State,
+fn root<W: WindowSystem>(
+    state: &mut State<W>,
     action: action::KeyPress,
     mod_not_shift: bool,
     mod_and_shift: bool,
@@ -282,13 +300,7 @@ fn root(
                 shift_window(state, Direction::North);
             }
             HDLKeysym::XK_r => {
-                let current_layout = state
-                    .monitors
-                    .get(&state.current_monitor)?
-                    .get_current_layout()?;
-                if current_layout == LayoutTag::Floating {
-                    wm::reorder(state);
-                }
+                reset_layout(state);
             }
             _ => (),
         }
@@ -306,21 +318,83 @@ fn root(
             HDLKeysym::XK_e => {
                 state.lib.exit();
             }
+            HDLKeysym::XK_r => {
+                let _ = state.tx.send(internal_action::InternalAction::Restart);
+            }
 
             HDLKeysym::XK_l => {
                 circulate_layout(state);
                 wm::reorder(state);
             }
+            HDLKeysym::XK_n => {
+                restore_picker(state);
+            }
             _ => (),
         }
     }
     Some(())
 }
 
-fn shift_window(state: &mut State, direction: Direction) -> Option<()> {
+const KEYBOARD_DRAG_STEP: i32 = 10;
+
+/*
+ * Arrow keys move or resize the window of a keyboard _NET_WM_MOVERESIZE,
+ * Return confirms and Escape puts the window back where it started
+ */
+fn keyboard_drag<W: WindowSystem>(state: &mut State<W>, action: action::KeyPress) -> Option<()> {
+    let w = state.drag_client?;
+    let (dx, dy) = match into_hdl_keysym(&state.lib.keycode_to_key_sym(action.keycode as u8)) {
+        HDLKeysym::XK_Left => (-KEYBOARD_DRAG_STEP, 0),
+        HDLKeysym::XK_Right => (KEYBOARD_DRAG_STEP, 0),
+        HDLKeysym::XK_Up => (0, -KEYBOARD_DRAG_STEP),
+        HDLKeysym::XK_Down => (0, KEYBOARD_DRAG_STEP),
+        HDLKeysym::XK_Return | HDLKeysym::XK_KP_Enter => {
+            wm::end_client_drag(state);
+            return Some(());
+        }
+        HDLKeysym::XK_Escape => {
+            let pos = Position {
+                x: state.drag_start_frame_pos.0,
+                y: state.drag_start_frame_pos.1,
+            };
+            let size = Size {
+                width: state.drag_start_frame_size.0 as i32,
+                height: state.drag_start_frame_size.1 as i32,
+            };
+            wm::end_client_drag(state);
+            let mon = state.monitors.get_mut(&wm::get_mon_by_window(state, w)?)?;
+            let ww = mon.get_client_mut(w)?;
+            ww.set_position(pos);
+            ww.set_size(size);
+            ww.handle_state = vec![HandleState::Move, HandleState::Resize].into();
+            return Some(());
+        }
+        _ => return Some(()),
+    };
+
+    let resize = state.drag_corner.is_some();
+    let mon = state.monitors.get_mut(&wm::get_mon_by_window(state, w)?)?;
+    let ww = mon.get_client(w)?.clone();
+    if resize {
+        let (_dec_size, size) =
+            mon.resize_window(w, ww.get_width() + dx, ww.get_height() + dy);
+        let ww = mon.get_client_mut(w)?;
+        ww.set_inner_size(size);
+        ww.handle_state = HandleState::Resize.into();
+    } else {
+        let pos = ww.get_position();
+        let (pos, _) = mon.move_window(w, pos.x + dx, pos.y + dy);
+        let ww = mon.get_client_mut(w)?;
+        ww.set_position(pos);
+        ww.handle_state = HandleState::Move.into();
+    }
+    Some(())
+}
+
+fn shift_window<W: WindowSystem>(state: &mut State<W>, direction: Direction) -> Option<()> {
     let mon = state.monitors.get_mut(&state.current_monitor)?;
     if mon.get_current_layout()? != LayoutTag::Floating {
-        let (newest, _) = mon.get_newest()?;
+        let (newest, _) = mon.get_master()?;
         if state.focus_w != *newest {
             match direction {
                 Direction::North => {
@@ -360,7 +434,11 @@ fn shift_window(state: &mut State, direction: Direction) -> Option<()> {
 
     for win in windows.into_iter() {
         mon.swap_window(win.window(), |_, ww| WindowWrapper {
-            previous_state: ww.current_state,
+            previous_state: if ww.current_state == WindowState::Snapped {
+                ww.previous_state
+            } else {
+                ww.current_state
+            },
             current_state: WindowState::Snapped,
             handle_state: HandleState::Shift.into(),
             ..win
@@ -369,10 +447,18 @@ fn shift_window(state: &mut State, direction: Direction) -> Option<()> {
     Some(())
 }
 
-fn swap_master(state: &mut State) -> Option<()> {
+fn reset_layout<W: WindowSystem>(state: &mut State<W>) -> Option<()> {
+    let mon = state.monitors.get_mut(&state.current_monitor)?;
+    if mon.get_current_layout()? != LayoutTag::Floating {
+        mon.get_current_ws_mut()?.proportions = Default::default();
+    }
+    wm::reorder(state)
+}
+
+fn swap_master<W: WindowSystem>(state: &mut State<W>) -> Option<()> {
     debug!("Swap master");
     let mon = state.monitors.get_mut(&state.current_monitor)?;
-    let newest = mon.get_newest()?.clone();
+    let newest = mon.get_master()?;
     match (newest.0.clone(), newest.1.clone()) {
         (win, client) => {
             if win != state.focus_w {
@@ -394,7 +480,7 @@ fn swap_master(state: &mut State) -> Option<()> {
     Some(())
 }
 
-fn circulate_layout(state: &mut State) -> Option<()> {
+fn circulate_layout<W: WindowSystem>(state: &mut State<W>) -> Option<()> {
     let mon = state.monitors.get_mut(&state.current_monitor)?;
     let ws = mon.get_current_ws_mut()?;
     ws.circulate_layout();
@@ -414,6 +500,56 @@ fn circulate_layout(state: &mut State) -> Option<()> {
     }
 }
 
+/*
+ * Lets the minimized windows be picked from dmenu, newest first, the pick is
+ * restored once dmenu exits. Without dmenu the newest one is restored
+ */
+fn restore_picker<W: WindowSystem>(state: &State<W>) -> Option<()> {
+    let newest = *state.minimized.last()?;
+    let entries = state
+        .minimized
+        .iter()
+        .rev()
+        .map(|w| (*w, state.lib.get_window_title(*w).unwrap_or_default()))
+        .collect::<Vec<_>>();
+    let tx = state.tx.clone();
+
+    std::thread::spawn(move || {
+        let child = Command::new("dmenu")
+            .args(["-i", "-l", "10", "-p", "restore:"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(_) => {
+                let _ = tx.send(internal_action::InternalAction::Restore(newest));
+                return;
+            }
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            let lines = entries
+                .iter()
+                .enumerate()
+                .map(|(i, (_, title))| format!("{} {}\n", i + 1, title))
+                .collect::<String>();
+            let _ = stdin.write_all(lines.as_bytes());
+        }
+        // SIGCHLD is ignored, dmenu is reaped by the kernel and can't be waited for
+        let mut line = String::new();
+        let picked = child
+            .stdout
+            .take()
+            .and_then(|mut stdout| stdout.read_to_string(&mut line).ok())
+            .and_then(|_| line.split_whitespace().next()?.parse::<usize>().ok())
+            .and_then(|i| entries.get(i.checked_sub(1)?));
+        if let Some((w, _)) = picked {
+            let _ = tx.send(internal_action::InternalAction::Restore(*w));
+        }
+    });
+    Some(())
+}
+
 fn keycode_to_ws(keycode: u8) -> u32 {
     ((keycode - 10) % 10) as u32
 }
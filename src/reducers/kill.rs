@@ -0,0 +1,36 @@
+#![allow(unused_imports)]
+use {
+    crate::{
+        models::HandleState,
+        state::State,
+        wm,
+        xlibwrapper::{action, window_system::WindowSystem},
+    },
+    reducer::*,
+};
+
+impl<W: WindowSystem> Reducer<action::Kill> for State<W> {
+    fn reduce(&mut self, action: action::Kill) {
+        // A window that reused the id of the closed one isn't killed for it
+        if self.closes.get(&action.win) != Some(&action.serial) {
+            return;
+        }
+        self.closes.remove(&action.win);
+        // Nothing to do if the client closed in time or is still answering pings,
+        // it is likely asking the user something before closing
+        let mon = match wm::get_mon_by_window(self, action.win) {
+            Some(mon) => mon,
+            None => return,
+        };
+        let responding = self
+            .monitors
+            .get(&mon)
+            .and_then(|mon| mon.workspaces.values().find_map(|ws| ws.clients.get(&action.win)))
+            .is_some_and(|ww| !ww.not_responding);
+        if responding && self.lib.supports_ping(action.win) && !self.pings.contains_key(&action.win)
+        {
+            return;
+        }
+        wm::push_handle_state(self, action.win, HandleState::Kill);
+    }
+}
@@ -4,8 +4,10 @@ use {
         config::CONFIG,
         layout::*,
         models::{rect::*, window_type::WindowType, windowwrapper::*, HandleState},
+        error::{self, HadlockError},
         state::State,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -15,21 +17,26 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::LeaveNotify> for State {
+impl<W: WindowSystem> Reducer<action::LeaveNotify> for State<W> {
     fn reduce(&mut self, action: action::LeaveNotify) {
-        //debug!("LeaveNotify");
+        error::report("LeaveNotify", leave_notify(self, action));
+    }
+}
 
-        let mon = self
-            .monitors
-            .get_mut(&self.current_monitor)
-            .expect("LeaveNotify - monitor - get_mut");
+fn leave_notify<W: WindowSystem>(
+    state: &mut State<W>,
+    action: action::LeaveNotify,
+) -> Result<(), HadlockError> {
+    //debug!("LeaveNotify");
 
-        if mon.get_current_layout() != Some(LayoutTag::Floating) {
-            return
-        }
+    let mon = state.monitor_mut(state.current_monitor)?;
+
+    if mon.get_current_layout() != Some(LayoutTag::Floating) {
+        return Ok(());
+    }
 
-        if let Some(w) = mon.get_client_mut(action.win) {
-            w.handle_state = HandleState::Unfocus.into();
-        }
+    if let Some(w) = mon.get_client_mut(action.win) {
+        w.handle_state = HandleState::Unfocus.into();
     }
+    Ok(())
 }
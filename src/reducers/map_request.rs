@@ -7,9 +7,11 @@ use {
             monitor::Monitor, rect::*, window_type::WindowType, windowwrapper::*, HandleState,
             WindowState,
         },
+        error::{self, HadlockError},
         state::State,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::masks::*,
         xlibwrapper::util::*,
@@ -20,182 +22,215 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::MapRequest> for State {
+impl<W: WindowSystem> Reducer<action::MapRequest> for State<W> {
     fn reduce(&mut self, action: action::MapRequest) {
-        match self.lib.transient_for_hint(action.win) {
-            Some(_win) => {
-                debug!(
-                    "Window type: {} is transient",
-                    self.lib.get_window_type(action.win).get_name()
-                );
-                let trans_size = {
-                    let attr = self.lib.get_window_attributes(action.win);
-                    Size {
-                        width: attr.width,
-                        height: attr.height,
-                    }
-                };
-                let mon = self
-                    .monitors
-                    .get_mut(&self.current_monitor)
-                    .expect("Shit went down in map_request");
+        error::report("MapRequest", map_request(self, action));
+    }
+}
 
-                let pos = match mon.get_client(action.parent) {
-                    Some(win) => {
-                        let (pos, size) = (win.get_position(), win.get_size());
-                        Position {
-                            x: pos.x + (size.width / 2) - (trans_size.width / 2) as i32,
-                            y: pos.y + (size.height / 2) - (trans_size.height / 2) as i32,
-                        }
+fn map_request<W: WindowSystem>(
+    state: &mut State<W>,
+    action: action::MapRequest,
+) -> Result<(), HadlockError> {
+    let layer = wm::initial_layer(state, action.win);
+    let sticky = state
+        .lib
+        .get_window_states_atoms(action.win)
+        .contains(&state.lib.xatom().NetWMStateSticky);
+    match state.lib.transient_for_hint(action.win) {
+        Some(_win) => {
+            debug!(
+                "Window type: {} is transient",
+                state.lib.get_window_type(action.win).get_name()
+            );
+            let trans_size = {
+                let geom = state.lib.get_geometry(action.win);
+                Size {
+                    width: geom.width as i32,
+                    height: geom.height as i32,
+                }
+            };
+            let mon_id = state.current_monitor;
+            let mon = state
+                .monitors
+                .get_mut(&mon_id)
+                .ok_or(HadlockError::NoMonitor(mon_id))?;
+
+            let pos = match mon.get_client(action.parent) {
+                Some(win) => {
+                    let (pos, size) = (win.get_position(), win.get_size());
+                    Position {
+                        x: pos.x + (size.width / 2) - (trans_size.width / 2) as i32,
+                        y: pos.y + (size.height / 2) - (trans_size.height / 2) as i32,
                     }
-                    None if action.parent == self.lib.get_root() => {
-                        let screen = mon.screen.clone();
-                        let (pos, size) = (
-                            Position {
-                                x: screen.x,
-                                y: screen.y,
-                            },
-                            Size {
-                                width: screen.width as i32,
-                                height: screen.height as i32,
-                            },
-                        );
+                }
+                None if action.parent == state.lib.get_root() => {
+                    let screen = mon.screen.clone();
+                    let (pos, size) = (
                         Position {
-                            x: pos.x + (size.width / 2) - (trans_size.width / 2) as i32,
-                            y: pos.y + (size.height / 2) - (trans_size.height / 2) as i32,
-                        }
-                    }
-                    None => return,
-                };
-                let ww = WindowWrapper::new(
-                    action.win,
-                    Rect::new(
-                        pos,
+                            x: screen.x,
+                            y: screen.y,
+                        },
                         Size {
-                            width: trans_size.width as i32,
-                            height: trans_size.height as i32,
+                            width: screen.width as i32,
+                            height: screen.height as i32,
                         },
-                    ),
-                    true,
-                );
-                mon.add_window(
-                    ww.window(),
-                    WindowWrapper {
-                        handle_state: HandleState::New.into(),
-                        ..ww
+                    );
+                    Position {
+                        x: pos.x + (size.width / 2) - (trans_size.width / 2) as i32,
+                        y: pos.y + (size.height / 2) - (trans_size.height / 2) as i32,
+                    }
+                }
+                None => return Ok(()),
+            };
+            let ww = WindowWrapper::new(
+                action.win,
+                Rect::new(
+                    pos,
+                    Size {
+                        width: trans_size.width as i32,
+                        height: trans_size.height as i32,
                     },
-                );
-                return;
-            }
-            None => (),
-        };
-        debug!(
-            "MapRequest - window: {} - Parent: {}",
-            action.win, action.parent
-        );
-        if self.lib.get_window_type(action.win) == WindowType::Dock {
-            match self.lib.get_window_strut_array(action.win) {
-                Some(dock) => {
-                    debug!("Mapping window is dock!");
-                    let w_geom = self.lib.get_geometry(action.win);
-                    let mon = self
-                        .monitors
-                        .values_mut()
-                        .filter(|mon| wm::window_inside_screen(&w_geom, &mon.screen))
-                        .collect::<Vec<&mut Monitor>>()
-                        .remove(0);
-                    mon.set_dock_area(dock);
-                    self.lib
-                        .select_input(action.win, PointerMotionMask | SubstructureRedirectMask);
-                    self.lib.map_window(action.win);
-                    return;
+                ),
+                true,
+            );
+            mon.add_window(
+                ww.window(),
+                WindowWrapper {
+                    handle_state: HandleState::New.into(),
+                    layer,
+                    ..ww
+                },
+            );
+            return Ok(());
+        }
+        None => (),
+    };
+    debug!(
+        "MapRequest - window: {} - Parent: {}",
+        action.win, action.parent
+    );
+    if state.lib.get_window_type(action.win) == WindowType::Dock {
+        match state.lib.get_window_strut_array(action.win) {
+            Some(dock) => {
+                debug!("Mapping window is dock!");
+                let w_geom = state.lib.get_geometry(action.win);
+                let mon = state
+                    .monitors
+                    .values_mut()
+                    .find(|mon| wm::window_inside_screen(&w_geom, &mon.screen))
+                    .ok_or(HadlockError::OffScreen)?;
+                mon.set_dock_area(dock);
+                wm::update_work_areas(state);
+                state.lib
+                    .select_input(action.win, PointerMotionMask | SubstructureRedirectMask);
+                state.lib.map_window(action.win);
+                if !state.dock_windows.contains(&action.win) {
+                    state.dock_windows.push(action.win);
                 }
-                None => return,
+                wm::raise_window(state, action.win);
+                return Ok(());
             }
+            None => return Ok(()),
         }
+    }
 
-        if !self.lib.should_be_managed(action.win) {
-            return;
+    if state.lib.get_window_type(action.win) == WindowType::Notification {
+        state.lib.map_window(action.win);
+        if !state.dock_windows.contains(&action.win) {
+            state.dock_windows.push(action.win);
         }
+        wm::raise_window(state, action.win);
+        return Ok(());
+    }
 
-        let mon = self
-            .monitors
-            .get_mut(&self.current_monitor)
-            .expect("MapRequest: get_client_mut");
+    if !state.lib.should_be_managed(action.win) {
+        return Ok(());
+    }
 
-        if mon.contains_window(action.win) {
-            let ww = match mon.remove_window(action.win) {
-                Some(ww) => ww,
-                None => {
-                    //let class = self.lib.get_class_hint(action.win);
-                    //debug!("{:?} not in ws: {}", class, mon.current_ws);
-                    return;
-                }
-            };
-            mon.add_window(
-                action.win,
-                WindowWrapper {
-                    handle_state: HandleState::Map.into(),
-                    ..ww
-                },
-            );
-            return;
-        } else {
-            if mon.contains_window(action.parent) {
-                self.lib.map_window(action.win);
-                self.lib.take_focus(action.win);
-                return;
+    if state.minimized.contains(&action.win) {
+        wm::restore(state, action.win);
+        return Ok(());
+    }
+
+    let mon_id = state.current_monitor;
+    let mon = state
+        .monitors
+        .get_mut(&mon_id)
+        .ok_or(HadlockError::NoMonitor(mon_id))?;
+
+    if mon.contains_window(action.win) {
+        let ww = match mon.remove_window(action.win) {
+            Some(ww) => ww,
+            None => {
+                //let class = state.lib.get_class_hint(action.win);
+                //debug!("{:?} not in ws: {}", class, mon.current_ws);
+                return Ok(());
             }
-            let windows = mon.place_window(action.win);
-            //debug!("Place in map_request: {:?}", windows);
-            debug!(
-                "Windows in mon before place_window: {:?}",
-                mon.get_current_ws()
-                    .unwrap()
-                    .clients
-                    .keys()
-                    .collect::<Vec<&Window>>()
-            );
-            let window_amount = windows.len();
-            let _ = windows.into_iter().for_each(|(win, rect)| {
-                match mon.remove_window(win) {
-                    Some(ww) => {
-                        let ww = WindowWrapper {
-                            window_rect: rect,
-                            current_state: WindowState::Free,
-                            handle_state: vec![HandleState::Move, HandleState::Resize].into(),
-                            ..ww
-                        };
-                        mon.add_window(win, ww);
-                    }
-                    None => {
-                        debug!("Mapping window not already in mon");
-                        let ww = {
-                            let ww = if window_amount == 1
-                                && mon.get_current_layout().unwrap() != LayoutTag::Floating
-                            {
-                                let mut ww = WindowWrapper::new(action.win, rect, false);
-                                ww.current_state = WindowState::Maximized;
-                                ww.handle_state
-                                    .replace(vec![HandleState::New, HandleState::Maximize]);
-                                ww
-                            } else {
-                                WindowWrapper::new(action.win, rect, false)
-                            };
+        };
+        mon.add_window(
+            action.win,
+            WindowWrapper {
+                handle_state: HandleState::Map.into(),
+                ..ww
+            },
+        );
+        return Ok(());
+    } else {
+        if mon.contains_window(action.parent) {
+            state.lib.map_window(action.win);
+            state.lib.take_focus(action.win);
+            return Ok(());
+        }
+        let windows = mon.place_window(action.win);
+        //debug!("Place in map_request: {:?}", windows);
+        debug!(
+            "Windows in mon before place_window: {:?}",
+            mon.get_current_windows()
+        );
+        let window_amount = windows.len();
+        let _ = windows.into_iter().for_each(|(win, rect)| {
+            match mon.remove_window(win) {
+                Some(ww) => {
+                    let ww = WindowWrapper {
+                        window_rect: rect,
+                        current_state: WindowState::Free,
+                        handle_state: vec![HandleState::Move, HandleState::Resize].into(),
+                        ..ww
+                    };
+                    mon.add_window(win, ww);
+                }
+                None => {
+                    debug!("Mapping window not already in mon");
+                    let ww = {
+                        let ww = if window_amount == 1
+                            && mon.get_current_layout() != Some(LayoutTag::Floating)
+                        {
+                            let mut ww = WindowWrapper::new(action.win, rect, false);
+                            ww.layer = layer;
+                            ww.current_state = WindowState::Maximized;
+                            ww.handle_state
+                                .replace(vec![HandleState::New, HandleState::Maximize]);
                             ww
+                        } else {
+                            WindowWrapper {
+                                layer,
+                                ..WindowWrapper::new(action.win, rect, false)
+                            }
                         };
-                        mon.add_window(action.win, ww);
-                    }
-                };
-            });
-            debug!(
-                "Windows in mon after place_window: {:?}",
-                mon.get_current_ws()
-                    .unwrap()
-                    .clients
-                    .keys()
-                    .collect::<Vec<&Window>>()
-            );
+                        ww
+                    };
+                    mon.add_window(action.win, ww);
+                }
+            };
+        });
+        debug!(
+            "Windows in mon after place_window: {:?}",
+            mon.get_current_windows()
+        );
+        if sticky {
+            wm::set_sticky(state, action.win, true);
         }
     }
+    Ok(())
 }
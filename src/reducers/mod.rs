@@ -3,13 +3,18 @@ mod button_release;
 mod client_message_request;
 mod configure_request;
 mod destroy;
+mod destroy_notify;
 mod enter_notify;
 mod focus;
 mod update_layout;
 mod key_press;
+mod kill;
 mod leave_notify;
 mod map_request;
 mod motion_notify;
+mod ping;
 mod property_notify;
+mod restore;
+mod restore_session;
 mod unknown;
 mod unmap_notify;
@@ -3,10 +3,15 @@ use {
     crate::{
         layout::LayoutTag,
         config::CONFIG,
-        models::{rect::*, window_type::WindowType, windowwrapper::*, HandleState, WindowState},
+        models::{
+            rect::*, window_type::WindowType, windowwrapper::*, Corner, HandleState, SnapZone,
+            WindowState,
+        },
+        error::{self, HadlockError},
         state::State,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::masks::*,
         xlibwrapper::util::*,
@@ -17,81 +22,335 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::MotionNotify> for State {
+impl<W: WindowSystem> Reducer<action::MotionNotify> for State<W> {
     fn reduce(&mut self, action: action::MotionNotify) {
-        let actual_mon = wm::get_monitor_by_point(self, action.x_root, action.y_root);
-        let old_mon = self.current_monitor;
-
-        if self.current_monitor != actual_mon {
-            self.current_monitor = actual_mon;
-            self.monitors
-                .get(&self.current_monitor)
-                .expect("MotionNotify - monitor - get_mut - change handle state")
-                .handle_state
-                .replace(HandleState::Focus);
-        }
+        error::report("MotionNotify", motion_notify(self, action));
+    }
+}
+
+fn motion_notify<W: WindowSystem>(
+    state: &mut State<W>,
+    action: action::MotionNotify,
+) -> Result<(), HadlockError> {
+    let action = match state.drag_client {
+        Some(_) if state.drag_keyboard => return Ok(()),
+        Some(w) => as_mod_drag(state, w, action),
+        None => action,
+    };
+    let actual_mon = wm::get_monitor_by_point(state, action.x_root, action.y_root);
+    let old_mon = state.current_monitor;
+
+    if state.current_monitor != actual_mon {
+        state.current_monitor = actual_mon;
+        state.monitor(actual_mon)?.handle_state.replace(HandleState::Focus);
+    }
+
+    let mon = state.monitor(state.current_monitor)?;
+    let layout = mon
+        .get_current_layout()
+        .ok_or(HadlockError::NoWorkspace(mon.current_ws))?;
+
+    if (action.state & (Button1Mask | Mod4Mask)) == Button1Mask | Mod4Mask
+        && drag_tiled(state, &action)
+    {
+        return Ok(());
+    }
+
+    let is_trans = match state.monitor(state.current_monitor)?.get_client(action.win) {
+        Some(client) => client.is_trans || client.floats(),
+        None => return Ok(()),
+    };
+
+
+    if layout == LayoutTag::ColumnMaster
+        && !is_trans
+        && (action.state & (Button3Mask | Mod4Mask)) == Button3Mask | Mod4Mask
+    {
+        resize_tiled(state, &action);
+        return Ok(());
+    }
+
+    if layout != LayoutTag::Floating && !is_trans {
+        return Ok(());
+    }
     
-        let layout = self.monitors
-            .get(&self.current_monitor)
-            .expect("MotionNotify - monitor - get - check layout")
-            .get_current_layout()
-            .expect("MotionNotify - monitor - get_current_layout");
-
-        let is_trans =  match self.monitors
-            .get(&self.current_monitor)
-            .expect("MotionNotify - monitor - get - action.win is_trans")
-            .get_client(action.win) {
-                Some(client) => {
-                    client.is_trans
-                },
-                None => { return }
+    if (action.state & (Button1Mask | Mod4Mask)) == Button1Mask | Mod4Mask {
+        if action.win == state.lib.get_root() {
+            return Ok(());
+        }
+
+        if action.win != state.lib.get_root() {
+            let ww = state
+                .monitor_mut(old_mon)?
+                .remove_window(action.win)
+                .ok_or(HadlockError::NoClient(action.win))?;
+            state.monitor_mut(actual_mon)?.add_window(action.win, ww);
+        }
+
+        let unsnapped = layout == LayoutTag::Floating && unsnap(state, &action);
+        let new_pos = calculcate_destination(state, &action);
+
+        let mon_id = state.current_monitor;
+        let mon = state
+            .monitors
+            .get_mut(&mon_id)
+            .ok_or(HadlockError::NoMonitor(mon_id))?;
+        let (pos, _) = mon.move_window(action.win, new_pos.x, new_pos.y);
+        let size = mon
+            .get_client(action.win)
+            .ok_or(HadlockError::NoClient(action.win))?
+            .get_size();
+        let pos = wm::snap_to_edges(mon, action.win, pos, size);
+
+        state.drag_snap_zone = if layout == LayoutTag::Floating {
+            SnapZone::at_point(&mon.screen, action.x_root, action.y_root)
+        } else {
+            None
+        };
+
+        let w = mon
+            .get_client_mut(action.win)
+            .ok_or(HadlockError::NoClient(action.win))?;
+        if w.current_state != WindowState::Monocle {
+            w.set_position(pos);
+            w.handle_state = if unsnapped {
+                vec![HandleState::Move, HandleState::Resize].into()
+            } else {
+                HandleState::Move.into()
+            };
+        }
+        return Ok(());
+    }
+
+    if (action.state & (Button3Mask | Mod4Mask)) == Button3Mask | Mod4Mask {
+        let corner = match state.drag_corner {
+            Some(corner) => corner,
+            None => return Ok(()),
+        };
+        if action.win == state.lib.get_root() {
+            return Ok(());
+        }
+        let mon_id = match wm::get_mon_by_window(state, action.win) {
+            Some(mon_id) => mon_id,
+            None => return Ok(()),
+        };
+
+        let hints = state.lib.get_size_hints(action.win);
+        let rect = calculate_resize(state, &action, corner, &hints);
+        let mon = state.monitor_mut(mon_id)?;
+        let (_dec_size, size) =
+            mon.resize_window(action.win, rect.get_size().width, rect.get_size().height);
+        let w = mon
+            .get_client_mut(action.win)
+            .ok_or(HadlockError::NoClient(action.win))?;
+        if w.current_state != WindowState::Monocle {
+            w.set_position(rect.get_position());
+            w.set_inner_size(size);
+            w.handle_state = vec![HandleState::Move, HandleState::Resize].into();
+        }
+    }
+    Ok(())
+}
+
+/*
+ * Motion of a drag started through _NET_WM_MOVERESIZE is reported on root with
+ * whatever button the client saw, it is handled as the Mod drag on the client
+ */
+fn as_mod_drag<W: WindowSystem>(
+    state: &State<W>,
+    w: Window,
+    action: action::MotionNotify,
+) -> action::MotionNotify {
+    let buttons = Button1Mask | Button2Mask | Button3Mask | Button4Mask | Button5Mask;
+    let button = match state.drag_corner {
+        Some(_) => Button3Mask,
+        None => Button1Mask,
+    };
+    action::MotionNotify {
+        win: w,
+        sub_win: w,
+        state: (action.state & !buttons) | button | Mod4Mask,
+        ..action
+    }
+}
+
+/*
+ * Mod+Button1 drag on a tiled window highlights the tiled window under the pointer,
+ * the two are swapped when the button is released
+ */
+fn drag_tiled<W: WindowSystem>(state: &mut State<W>, action: &action::MotionNotify) -> bool {
+    let mon = match wm::get_mon_by_window(state, action.win).and_then(|id| state.monitors.get(&id)) {
+        Some(mon) => mon,
+        None => return false,
+    };
+    match (mon.get_current_layout(), mon.get_client(action.win)) {
+        (Some(layout), Some(ww))
+            if layout != LayoutTag::Floating && !ww.is_trans && !ww.floats() => {}
+        _ => return false,
+    }
+
+    let target = wm::tiled_window_at(state, action.win, action.x_root, action.y_root);
+    if target != state.drag_target {
+        if let Some(old_target) = state.drag_target {
+            wm::push_handle_state(state, old_target, HandleState::DropTargetClear);
+        }
+        if let Some(new_target) = target {
+            wm::push_handle_state(state, new_target, HandleState::DropTarget);
+        }
+        state.drag_target = target;
+    }
+    true
+}
+
+const MIN_MASTER_RATIO: f32 = 0.1;
+const MAX_MASTER_RATIO: f32 = 0.9;
+const MIN_TILE_HEIGHT: i32 = 40;
+
+/*
+ * Mod+Button3 drag on a tiled window moves the master/stack boundary horizontally
+ * and the boundary to the neighbouring stack window, above or below depending on
+ * the grabbed corner, vertically
+ */
+fn resize_tiled<W: WindowSystem>(
+    state: &mut State<W>,
+    action: &action::MotionNotify,
+) -> Option<()> {
+    let corner = state.drag_corner?;
+    let (delta_x, delta_y) = (
+        action.x_root - state.drag_start_pos.0,
+        action.y_root - state.drag_start_pos.1,
+    );
+    let (start_x, _) = state.drag_start_frame_pos;
+    let (start_width, start_height) = (
+        state.drag_start_frame_size.0 as i32,
+        state.drag_start_frame_size.1 as i32,
+    );
+    let border = CONFIG.border_width;
+
+    let mon = state.monitors.get_mut(&state.current_monitor)?;
+    let master = *mon.get_master()?.0;
+    let screen = mon.screen.clone();
+
+    let start_split = if action.win == master {
+        start_x + start_width + 2 * border + CONFIG.inner_gap / 2
+    } else {
+        start_x - CONFIG.inner_gap / 2
+    };
+    let ratio = (start_split + delta_x - screen.x) as f32 / screen.width as f32;
+
+    let ws = mon.get_current_ws_mut()?;
+    ws.proportions.master_ratio = ratio.clamp(MIN_MASTER_RATIO, MAX_MASTER_RATIO);
+
+    if action.win != master {
+        let stack = ws
+            .clients
+            .iter()
+            .filter(|(w, ww)| **w != master && ww.tiles())
+            .map(|(w, _)| *w)
+            .collect::<Vec<Window>>();
+        let index = stack.iter().position(|w| *w == action.win)?;
+        let (neighbour, new_height) = match corner.vertical() {
+            -1 if index > 0 => (stack.get(index - 1), start_height + 2 * border - delta_y),
+            1 => (stack.get(index + 1), start_height + 2 * border + delta_y),
+            _ => (None, 0),
         };
 
+        if let Some(neighbour) = neighbour.copied() {
+            let pair_height = ws.clients.get(&action.win)?.get_height()
+                + ws.clients.get(&neighbour)?.get_height()
+                + 4 * border;
+            let min_height = MIN_TILE_HEIGHT + 2 * border;
+            let new_height = new_height.max(min_height).min(pair_height - min_height);
 
-        if layout != LayoutTag::Floating && !is_trans { return }
-        
-        let new_pos = calculcate_destination(self, &action);
-
-        if (action.state & (Button1Mask | Mod4Mask)) == Button1Mask | Mod4Mask {
-            if action.win == self.lib.get_root() {
-                return;
-            }
-
-            if action.win != self.lib.get_root() {
-                let ww = self
-                    .monitors
-                    .get_mut(&old_mon)
-                    .expect("MotionNotify - old_mon - get_mut")
-                    .remove_window(action.win).expect("Trying to remove window in motion_notify");
-
-                let _ = self
-                    .monitors
-                    .get_mut(&actual_mon)
-                    .expect("MotionNotify - old_mon - get_mut")
-                    .add_window(action.win, ww);
-            }
-
-            let (pos, _) = self
-                .monitors
-                .get_mut(&self.current_monitor)
-                .expect("MotionNotify - monitor - get_mut")
-                .move_window(action.win, new_pos.x, new_pos.y);
-            let w = self
-                .monitors
-                .get_mut(&self.current_monitor)
-                .expect("MotionNotify - monitor - get_mut")
-                .get_client_mut(action.win)
-                .expect("motion_notify some window");
-            if w.current_state != WindowState::Monocle {
-                w.set_position(pos);
-                w.handle_state = HandleState::Move.into();
-            }
-            return;
+            let pair_weight =
+                ws.proportions.weight(action.win) + ws.proportions.weight(neighbour);
+            let weight = pair_weight * new_height as f32 / pair_height as f32;
+            ws.proportions.weights.insert(action.win, weight);
+            ws.proportions
+                .weights
+                .insert(neighbour, pair_weight - weight);
         }
     }
+
+    wm::reorder(state)
+}
+
+/*
+ * Dragging a snapped window away restores the size it had before it was snapped,
+ * keeping the pointer at the same relative spot of the title/top edge
+ */
+fn unsnap<W: WindowSystem>(state: &mut State<W>, action: &action::MotionNotify) -> bool {
+    let (delta_x, delta_y) = (
+        action.x_root - state.drag_start_pos.0,
+        action.y_root - state.drag_start_pos.1,
+    );
+    if delta_x.abs().max(delta_y.abs()) <= CONFIG.snap_threshold {
+        return false;
+    }
+
+    let ww = match state
+        .monitors
+        .get_mut(&state.current_monitor)
+        .and_then(|mon| mon.get_client_mut(action.win))
+    {
+        Some(ww) if ww.snap_zone.is_some() => ww,
+        _ => return false,
+    };
+
+    let restore_size = ww.restore_size;
+    let grab_x = (state.drag_start_pos.0 - state.drag_start_frame_pos.0) * restore_size.width
+        / (state.drag_start_frame_size.0.max(1) as i32);
+    state.drag_start_frame_pos.0 = state.drag_start_pos.0 - grab_x;
+    state.drag_start_frame_size = (restore_size.width as u32, restore_size.height as u32);
+
+    ww.set_size(restore_size);
+    ww.snap_zone = None;
+    ww.set_window_state(WindowState::Free);
+    true
+}
+
+/*
+ * Resizes from the corner grabbed in ButtonPress while keeping the opposite corner in place
+ */
+fn calculate_resize<W: WindowSystem>(
+    state: &State<W>,
+    action: &action::MotionNotify,
+    corner: Corner,
+    hints: &SizeHints,
+) -> Rect {
+    let (delta_x, delta_y) = (
+        action.x_root - state.drag_start_pos.0,
+        action.y_root - state.drag_start_pos.1,
+    );
+    let (start_x, start_y) = state.drag_start_frame_pos;
+    let (start_width, start_height) = (
+        state.drag_start_frame_size.0 as i32,
+        state.drag_start_frame_size.1 as i32,
+    );
+
+    let (width, height) = (
+        start_width + corner.horizontal() * delta_x,
+        start_height + corner.vertical() * delta_y,
+    );
+    let size = hints.constrain(Size { width, height });
+
+    let x = if corner.horizontal() < 0 {
+        start_x + start_width - size.width
+    } else {
+        start_x
+    };
+    let y = if corner.vertical() < 0 {
+        start_y + start_height - size.height
+    } else {
+        start_y
+    };
+    Rect::new(Position { x, y }, size)
 }
 
-fn calculcate_destination(state: &State, action: &action::MotionNotify) -> Position {
+fn calculcate_destination<W: WindowSystem>(
+    state: &State<W>,
+    action: &action::MotionNotify,
+) -> Position {
 
         let drag_pos = Position {
             x: action.x_root,
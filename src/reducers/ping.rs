@@ -0,0 +1,36 @@
+#![allow(unused_imports)]
+use {
+    crate::{
+        state::State,
+        wm,
+        xlibwrapper::{action, window_system::WindowSystem},
+    },
+    notify_rust::{Notification, Timeout},
+    reducer::*,
+};
+
+impl<W: WindowSystem> Reducer<action::PingFocused> for State<W> {
+    fn reduce(&mut self, _action: action::PingFocused) {
+        if self.focus_w != self.lib.get_root() {
+            wm::ping(self, self.focus_w);
+        }
+    }
+}
+
+impl<W: WindowSystem> Reducer<action::PingTimeout> for State<W> {
+    fn reduce(&mut self, action: action::PingTimeout) {
+        if self.pings.get(&action.win) != Some(&action.serial) {
+            return;
+        }
+        // The ping stays out so a late answer still marks the window as responding
+        if wm::set_responding(self, action.win, false).is_none() {
+            return;
+        }
+        let title = self.lib.get_window_title(action.win).unwrap_or_default();
+        let _ = Notification::new()
+            .summary("Window not responding")
+            .body(&format!("{} is not responding, shift + mod + x kills it", title))
+            .timeout(Timeout::Milliseconds(5000))
+            .show();
+    }
+}
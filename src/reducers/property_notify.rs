@@ -6,6 +6,7 @@ use {
         state::State,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -15,7 +16,7 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::PropertyNotify> for State {
+impl<W: WindowSystem> Reducer<action::PropertyNotify> for State<W> {
     fn reduce(&mut self, _action: action::PropertyNotify) {
         debug!("PropertyNotify");
     }
@@ -0,0 +1,15 @@
+#![allow(unused_imports)]
+use {
+    crate::{
+        state::State,
+        wm,
+        xlibwrapper::{action, window_system::WindowSystem},
+    },
+    reducer::*,
+};
+
+impl<W: WindowSystem> Reducer<action::Restore> for State<W> {
+    fn reduce(&mut self, action: action::Restore) {
+        wm::restore(self, action.win);
+    }
+}
@@ -0,0 +1,15 @@
+use {
+    crate::{
+        error,
+        session,
+        state::State,
+        xlibwrapper::{action, window_system::WindowSystem},
+    },
+    reducer::*,
+};
+
+impl<W: WindowSystem> Reducer<action::RestoreSession> for State<W> {
+    fn reduce(&mut self, action: action::RestoreSession) {
+        error::report("RestoreSession", session::restore(self, action.session));
+    }
+}
@@ -5,6 +5,7 @@ use {
         models::{rect::*, window_type::WindowType, windowwrapper::WindowWrapper},
         state::State,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -13,6 +14,6 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::UnknownEvent> for State {
+impl<W: WindowSystem> Reducer<action::UnknownEvent> for State<W> {
     fn reduce(&mut self, _action: action::UnknownEvent) {}
 }
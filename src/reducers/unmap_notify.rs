@@ -5,6 +5,7 @@ use {
         models::{rect::*, window_type::WindowType, windowwrapper::*},
         state::State,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -14,9 +15,24 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::UnmapNotify> for State {
-    fn reduce(&mut self, _action: action::UnmapNotify) {
+impl<W: WindowSystem> Reducer<action::UnmapNotify> for State<W> {
+    fn reduce(&mut self, action: action::UnmapNotify) {
         //debug!("UnmapNotify");
         //self.lib.unmap_window(action.win);
+
+        // Windows hadlock unmapped itself, e.g. of a hidden workspace, stay managed
+        {
+            let mut unmaps = self.unmaps.borrow_mut();
+            if let Some(count) = unmaps.get_mut(&action.win) {
+                *count -= 1;
+                if *count == 0 {
+                    unmaps.remove(&action.win);
+                }
+                return;
+            }
+        }
+        // Any other unmap withdraws the window, it is forgotten like a destroyed one
+        // and added again when it maps. Docks and notifications go the same way
+        self.reduce(action::DestroyNotify { win: action.win });
     }
 }
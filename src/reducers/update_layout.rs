@@ -7,9 +7,11 @@ use {
             WindowState, 
         },
         layout::LayoutTag,
+        error::{self, HadlockError},
         state::State,
         wm,
         xlibwrapper::action,
+        xlibwrapper::window_system::WindowSystem,
         xlibwrapper::core::*,
         xlibwrapper::util::*,
         xlibwrapper::xlibmodels::*,
@@ -19,18 +21,19 @@ use {
     std::rc::Rc,
 };
 
-impl Reducer<action::UpdateLayout> for State {
+impl<W: WindowSystem> Reducer<action::UpdateLayout> for State<W> {
     fn reduce(&mut self, _action: action::UpdateLayout) {
-        if self
-            .monitors
-            .get(&self.current_monitor)
-            .expect("")
-            .get_current_ws()
-            .expect("")
-            .get_current_layout()
-            != LayoutTag::Floating
-        {
-            wm::reorder(self);
-        }
+        error::report("UpdateLayout", update_layout(self));
     }
 }
+
+fn update_layout<W: WindowSystem>(state: &mut State<W>) -> Result<(), HadlockError> {
+    let mon = state.monitor(state.current_monitor)?;
+    let layout = mon
+        .get_current_layout()
+        .ok_or(HadlockError::NoWorkspace(mon.current_ws))?;
+    if layout != LayoutTag::Floating {
+        wm::reorder(state);
+    }
+    Ok(())
+}
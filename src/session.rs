@@ -0,0 +1,264 @@
+use {
+    crate::{
+        error::HadlockError,
+        layout::{LayoutTag, Proportions},
+        models::{
+            internal_action::InternalAction, rect::Rect, windowwrapper::WindowWrapper,
+            workspace::Workspace, HandleState, Layer, SnapZone, WindowState,
+        },
+        state::State,
+        xlibwrapper::{
+            util::{Position, Size},
+            window_system::WindowSystem,
+            xlibmodels::{MonitorId, Window},
+        },
+        HadlockResult,
+    },
+    serde::{Deserialize, Serialize},
+    std::{env, fs, path::PathBuf, time::Duration},
+};
+
+/* A restart takes a moment, older sessions were left behind by something else */
+const MAX_AGE: Duration = Duration::from_secs(30);
+
+/*
+ * What a restart hands over to the new binary: where every client was, how
+ * each workspace was laid out and what had focus
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub focus: Window,
+    pub current_monitor: MonitorId,
+    pub minimized: Vec<Window>,
+    pub monitors: Vec<SavedMonitor>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedMonitor {
+    pub id: MonitorId,
+    pub current_ws: u32,
+    pub workspaces: Vec<SavedWorkspace>,
+}
+
+/* Clients are kept in workspace order, the tiling depends on it */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedWorkspace {
+    pub tag: u32,
+    pub layout: LayoutTag,
+    pub proportions: Proportions,
+    pub clients: Vec<SavedClient>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedClient {
+    pub window: Window,
+    pub window_rect: Rect,
+    pub restore_position: Position,
+    pub restore_size: Size,
+    pub current_state: WindowState,
+    pub previous_state: WindowState,
+    pub is_floating: bool,
+    pub is_sticky: bool,
+    pub layer: Layer,
+    pub snap_zone: Option<SnapZone>,
+}
+
+impl Session {
+    pub fn from_state<W: WindowSystem>(state: &State<W>) -> Self {
+        let mut monitors = state
+            .monitors
+            .values()
+            .map(|mon| {
+                let mut workspaces = mon
+                    .workspaces
+                    .values()
+                    .map(|ws| SavedWorkspace {
+                        tag: ws.tag,
+                        layout: ws.get_current_layout(),
+                        proportions: ws.proportions.clone(),
+                        clients: ws.clients.values().map(SavedClient::from).collect(),
+                    })
+                    .collect::<Vec<SavedWorkspace>>();
+                workspaces.sort_by_key(|ws| ws.tag);
+                SavedMonitor {
+                    id: mon.id,
+                    current_ws: mon.current_ws,
+                    workspaces,
+                }
+            })
+            .collect::<Vec<SavedMonitor>>();
+        monitors.sort_by_key(|mon| mon.id);
+        Self {
+            focus: state.focus_w,
+            current_monitor: state.current_monitor,
+            minimized: state.minimized.clone(),
+            monitors,
+        }
+    }
+
+    /* Drops the clients that aren't in windows, they went away during the restart */
+    pub fn retain(&mut self, windows: &[Window]) {
+        self.monitors
+            .iter_mut()
+            .flat_map(|mon| mon.workspaces.iter_mut())
+            .for_each(|ws| ws.clients.retain(|client| windows.contains(&client.window)));
+        self.minimized.retain(|w| windows.contains(w));
+    }
+
+    pub fn clients(&self) -> Vec<Window> {
+        self.monitors
+            .iter()
+            .flat_map(|mon| mon.workspaces.iter())
+            .flat_map(|ws| ws.clients.iter().map(|client| client.window))
+            .collect()
+    }
+
+    pub fn save(&self) -> HadlockResult<PathBuf> {
+        let path = path();
+        fs::write(&path, serde_json::to_string(self)?)?;
+        Ok(path)
+    }
+
+    /*
+     * The session a restart of hadlock on this display left behind. The file is
+     * removed so it is only restored once, sessions older than MAX_AGE are ignored
+     */
+    pub fn take() -> Option<Self> {
+        let path = path();
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        let session = fs::read_to_string(&path).ok()?;
+        let _ = fs::remove_file(&path);
+        if age.is_none_or(|age| age > MAX_AGE) {
+            warn!("Ignoring session in {:?}, it is older than {:?}", path, MAX_AGE);
+            return None;
+        }
+        match serde_json::from_str(&session) {
+            Ok(session) => Some(session),
+            Err(e) => {
+                error!("Ignoring session in {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /* Removes the saved session, e.g. when the restart it was saved for failed */
+    pub fn discard() {
+        let _ = fs::remove_file(path());
+    }
+}
+
+impl From<&WindowWrapper> for SavedClient {
+    fn from(ww: &WindowWrapper) -> Self {
+        Self {
+            window: ww.window(),
+            window_rect: ww.window_rect,
+            restore_position: ww.restore_position,
+            restore_size: ww.restore_size,
+            current_state: ww.current_state,
+            previous_state: ww.previous_state,
+            is_floating: ww.is_floating,
+            is_sticky: ww.is_sticky,
+            layer: ww.layer,
+            snap_zone: ww.snap_zone,
+        }
+    }
+}
+
+/* One file per display, window ids of one X server mean nothing to another */
+fn path() -> PathBuf {
+    let display = env::var("DISPLAY").unwrap_or_default().replace('/', "_");
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join(format!("hadlock-session-{}.json", display))
+}
+
+/*
+ * Puts the clients of session back where they were. Monitors that are gone
+ * hand their workspaces to the current monitor. Clients of hidden workspaces
+ * and minimized ones are managed but unmapped right away
+ */
+pub fn restore<W: WindowSystem>(state: &mut State<W>, session: Session) -> Result<(), HadlockError> {
+    let focus = Some(session.focus).filter(|w| session.clients().contains(w));
+    for saved_mon in session.monitors {
+        let mon_id = if state.monitors.contains_key(&saved_mon.id) {
+            saved_mon.id
+        } else {
+            state.current_monitor
+        };
+        let mon = state
+            .monitors
+            .get_mut(&mon_id)
+            .ok_or(HadlockError::NoMonitor(mon_id))?;
+        if mon_id == saved_mon.id {
+            mon.current_ws = saved_mon.current_ws;
+            mon.handle_state.replace(HandleState::Focus);
+        }
+        for saved_ws in saved_mon.workspaces {
+            let visible = saved_ws.tag == mon.current_ws;
+            let ws = mon
+                .workspaces
+                .entry(saved_ws.tag)
+                .or_insert_with(|| Workspace::new(saved_ws.tag));
+            ws.set_layout(saved_ws.layout);
+            ws.proportions = saved_ws.proportions;
+            for client in saved_ws.clients {
+                let is_minimized = session.minimized.contains(&client.window);
+                let mut handle_state = vec![HandleState::New];
+                if !visible || is_minimized {
+                    handle_state.push(HandleState::Unmap);
+                }
+                let ww = WindowWrapper {
+                    restore_position: client.restore_position,
+                    restore_size: client.restore_size,
+                    current_state: client.current_state,
+                    previous_state: client.previous_state,
+                    is_floating: client.is_floating,
+                    is_sticky: client.is_sticky,
+                    is_minimized,
+                    layer: client.layer,
+                    snap_zone: client.snap_zone,
+                    handle_state: handle_state.into(),
+                    ..WindowWrapper::new(client.window, client.window_rect, false)
+                };
+                ws.add_window(client.window, ww);
+            }
+        }
+    }
+    if state.monitors.contains_key(&session.current_monitor) {
+        state.current_monitor = session.current_monitor;
+    }
+    state.minimized = session.minimized;
+    if let Some(w) = focus {
+        let _ = state.tx.send(InternalAction::FocusSpecific(w));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{fs::File, time::SystemTime};
+
+    #[test]
+    fn old_sessions_are_ignored() {
+        let session = Session {
+            focus: 0,
+            current_monitor: 0,
+            minimized: vec![],
+            monitors: vec![],
+        };
+        let path = session.save().expect("saved");
+        File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now() - MAX_AGE * 2))
+            .expect("modified time");
+
+        assert!(Session::take().is_none());
+        assert!(!path.exists());
+    }
+}
@@ -1,19 +1,22 @@
 use {
-    crate::models::{monitor::Monitor, windowwrapper::WindowWrapper, workspace::Workspace},
-    crate::xlibwrapper::{core::*, xlibmodels::*},
+    crate::models::{monitor::Monitor, windowwrapper::WindowWrapper, workspace::Workspace, Corner, SnapZone},
+    crate::xlibwrapper::{window_system::WindowSystem, xlibmodels::*},
     crate::models::internal_action,
+    crate::error::HadlockError,
+    crate::wm,
     derivative::*,
+    std::cell::RefCell,
     std::collections::HashMap,
     std::rc::Rc,
-    std::sync::mpsc::Sender,
 };
 
 #[derive(Derivative)]
-#[derivative(Debug)]
-pub struct State {
+#[derivative(Debug(bound = ""))]
+pub struct State<W: WindowSystem> {
     #[derivative(Debug = "ignore")]
-    pub lib: Rc<XlibWrapper>,
-    pub tx: Sender<internal_action::InternalAction>,
+    pub lib: Rc<W>,
+    #[derivative(Debug = "ignore")]
+    pub tx: internal_action::ActionSender,
     pub windows: HashMap<Window, WindowWrapper>,
     pub focus_w: Window,
     pub monitors: HashMap<MonitorId, Monitor>,
@@ -21,10 +24,23 @@ pub struct State {
     pub drag_start_pos: (i32, i32),
     pub drag_start_frame_pos: (i32, i32),
     pub drag_start_frame_size: (u32, u32),
+    pub drag_corner: Option<Corner>,
+    pub drag_snap_zone: Option<SnapZone>,
+    pub drag_target: Option<Window>,
+    pub drag_client: Option<Window>,
+    pub drag_keyboard: bool,
+    pub dock_windows: Vec<Window>,
+    pub stack: RefCell<Vec<Window>>,
+    pub minimized: Vec<Window>,
+    pub pings: HashMap<Window, i64>,
+    pub ping_serial: i64,
+    pub closes: HashMap<Window, i64>,
+    pub close_serial: i64,
+    pub unmaps: RefCell<HashMap<Window, u32>>,
 }
 
-impl State {
-    pub fn new(lib: Rc<XlibWrapper>, tx: Sender<internal_action::InternalAction>) -> Self {
+impl<W: WindowSystem> State<W> {
+    pub fn new(lib: Rc<W>, tx: internal_action::ActionSender) -> Self {
         let focus_w = lib.get_root();
         let monitors = {
             let mut monitors = HashMap::default();
@@ -39,7 +55,7 @@ impl State {
             debug!("Monitor on start: {}", mon_count);
             monitors
         };
-        Self {
+        let state = Self {
             lib,
             tx,
             windows: HashMap::default(),
@@ -49,6 +65,29 @@ impl State {
             drag_start_pos: (0, 0),
             drag_start_frame_pos: (0, 0),
             drag_start_frame_size: (0, 0),
-        }
+            drag_corner: None,
+            drag_snap_zone: None,
+            drag_target: None,
+            drag_client: None,
+            drag_keyboard: false,
+            dock_windows: vec![],
+            stack: RefCell::new(vec![]),
+            minimized: vec![],
+            pings: HashMap::new(),
+            ping_serial: 0,
+            closes: HashMap::new(),
+            close_serial: 0,
+            unmaps: RefCell::new(HashMap::new()),
+        };
+        wm::update_work_areas(&state);
+        state
+    }
+
+    pub fn monitor(&self, id: MonitorId) -> Result<&Monitor, HadlockError> {
+        self.monitors.get(&id).ok_or(HadlockError::NoMonitor(id))
+    }
+
+    pub fn monitor_mut(&mut self, id: MonitorId) -> Result<&mut Monitor, HadlockError> {
+        self.monitors.get_mut(&id).ok_or(HadlockError::NoMonitor(id))
     }
 }
@@ -1,13 +1,14 @@
 #![allow(dead_code)]
 
 use crate::{
-    layout::LayoutTag,
+    config::CONFIG,
+    layout::{self, LayoutTag},
     models::{
-        monitor::Monitor, rect::*, screen::*, windowwrapper::*, workspace::*, HandleState,
-        WindowState,
+        internal_action::InternalAction, monitor::Monitor, rect::*, screen::*,
+        window_type::WindowType, windowwrapper::*, workspace::*, HandleState, Layer, WindowState,
     },
     state::State,
-    xlibwrapper::{util::*, xlibmodels::*},
+    xlibwrapper::{util::*, window_system::WindowSystem, xlibmodels::*},
 };
 
 pub fn window_inside_screen(w_geom: &Geometry, screen: &Screen) -> bool {
@@ -16,6 +17,52 @@ pub fn window_inside_screen(w_geom: &Geometry, screen: &Screen) -> bool {
     inside_width && inside_height
 }
 
+/*
+ * Snaps the outer edges of a dragged window to the screen and dock edges and to
+ * the edges of the other windows on the workspace within CONFIG.snap_threshold
+ */
+pub fn snap_to_edges(mon: &Monitor, w: Window, pos: Position, size: Size) -> Position {
+    if CONFIG.snap_threshold <= 0 {
+        return pos;
+    }
+    let screen = &mon.screen;
+    let dock_height = match mon.dock_area.as_rect(screen) {
+        Some(dock) => dock.get_size().height,
+        None => 0,
+    };
+    let width = size.width + 2 * CONFIG.border_width;
+    let height = size.height + 2 * CONFIG.border_width;
+
+    let mut edges_x = vec![screen.x, screen.x + screen.width];
+    let mut edges_y = vec![screen.y + dock_height, screen.y + screen.height];
+    mon.get_snap_targets(w).iter().for_each(|rect| {
+        let (t_pos, t_size) = (rect.get_position(), rect.get_size());
+        let overlaps_y = pos.y < t_pos.y + t_size.height && t_pos.y < pos.y + height;
+        let overlaps_x = pos.x < t_pos.x + t_size.width && t_pos.x < pos.x + width;
+        if overlaps_y {
+            edges_x.push(t_pos.x);
+            edges_x.push(t_pos.x + t_size.width);
+        }
+        if overlaps_x {
+            edges_y.push(t_pos.y);
+            edges_y.push(t_pos.y + t_size.height);
+        }
+    });
+
+    Position {
+        x: closest_edge(pos.x, width, &edges_x).unwrap_or(pos.x),
+        y: closest_edge(pos.y, height, &edges_y).unwrap_or(pos.y),
+    }
+}
+
+fn closest_edge(start: i32, length: i32, edges: &[i32]) -> Option<i32> {
+    edges
+        .iter()
+        .flat_map(|edge| vec![*edge, *edge - length])
+        .filter(|candidate| (candidate - start).abs() <= CONFIG.snap_threshold)
+        .min_by_key(|candidate| (candidate - start).abs())
+}
+
 pub fn toggle_maximize(mon: &Monitor, ww: WindowWrapper) -> WindowWrapper {
     let ww_state = ww.current_state;
     match ww_state {
@@ -72,7 +119,7 @@ pub fn toggle_monocle(mon: &Monitor, ww: WindowWrapper) -> WindowWrapper {
     }
 }
 
-pub fn get_mon_by_ws(state: &State, ws: u32) -> Option<MonitorId> {
+pub fn get_mon_by_ws<W: WindowSystem>(state: &State<W>, ws: u32) -> Option<MonitorId> {
     let mut ret_vec = state
         .monitors
         .iter()
@@ -86,7 +133,7 @@ pub fn get_mon_by_ws(state: &State, ws: u32) -> Option<MonitorId> {
     }
 }
 
-pub fn get_mon_by_window(state: &State, w: Window) -> Option<MonitorId> {
+pub fn get_mon_by_window<W: WindowSystem>(state: &State<W>, w: Window) -> Option<MonitorId> {
     let mut ret_vec = state
         .monitors
         .iter()
@@ -100,7 +147,106 @@ pub fn get_mon_by_window(state: &State, w: Window) -> Option<MonitorId> {
     }
 }
 
-pub fn set_current_ws(state: &mut State, ws: u32) -> Option<()> {
+pub fn get_ws_by_window<W: WindowSystem>(state: &State<W>, w: Window) -> Option<u32> {
+    state
+        .monitors
+        .get(&get_mon_by_window(state, w)?)?
+        .workspaces
+        .values()
+        .find(|ws| ws.contains_window(w))
+        .map(|ws| ws.tag)
+}
+
+/*
+ * _NET_DESKTOP_GEOMETRY spans every monitor, _NET_WORKAREA of a desktop is the
+ * work area of the monitor it is (or would be) shown on
+ */
+pub fn update_work_areas<W: WindowSystem>(state: &State<W>) {
+    let (width, height) = state.monitors.values().fold((0, 0), |(w, h), mon| {
+        (
+            w.max(mon.screen.x + mon.screen.width),
+            h.max(mon.screen.y + mon.screen.height),
+        )
+    });
+    state.lib.update_net_desktop_geometry(Size { width, height });
+
+    let work_areas = (0..CONFIG.workspaces.len() as u32)
+        .filter_map(|ws| {
+            let mon_id = get_mon_by_ws(state, ws).unwrap_or(state.current_monitor);
+            state.monitors.get(&mon_id).map(|mon| mon.work_area())
+        })
+        .collect::<Vec<Rect>>();
+    state.lib.update_net_workarea(&work_areas);
+}
+
+/* Puts w on top of its layer the next time windows are restacked */
+pub fn push_stack<W: WindowSystem>(state: &State<W>, w: Window) {
+    let mut stack = state.stack.borrow_mut();
+    stack.retain(|x| *x != w);
+    stack.push(w);
+}
+
+pub fn raise_window<W: WindowSystem>(state: &State<W>, w: Window) {
+    push_stack(state, w);
+    restack(state);
+}
+
+/*
+ * Restacks every window hadlock knows of by layer, transients go above the other
+ * windows of their layer, the rest keeps the order they were raised in
+ */
+pub fn restack<W: WindowSystem>(state: &State<W>) {
+    let windows = {
+        let stack = state.stack.borrow();
+        let raised = |w: Window| stack.iter().position(|x| *x == w).map_or(0, |i| i + 1);
+        let mut windows = state
+            .monitors
+            .values()
+            .flat_map(|mon| mon.workspaces.values())
+            .flat_map(|ws| ws.clients.values())
+            .map(|ww| {
+                let w = ww.get_dec().unwrap_or(ww.window());
+                ((ww.get_layer(), ww.is_trans, raised(ww.window())), w)
+            })
+            .chain(
+                state
+                    .dock_windows
+                    .iter()
+                    .map(|w| ((Layer::Dock, false, raised(*w)), *w)),
+            )
+            .collect::<Vec<((Layer, bool, usize), Window)>>();
+        windows.sort_by_key(|(key, _)| std::cmp::Reverse(*key));
+        windows.into_iter().map(|(_, w)| w).collect::<Vec<Window>>()
+    };
+    state.lib.restack_windows(windows);
+    update_client_list_stacking(state);
+}
+
+/* Layer a new window asks for through its type and _NET_WM_STATE */
+pub fn initial_layer<W: WindowSystem>(state: &State<W>, w: Window) -> Layer {
+    let states = state.lib.get_window_states_atoms(w);
+    if state.lib.get_window_type(w) == WindowType::Desktop {
+        Layer::Desktop
+    } else if states.contains(&state.lib.xatom().NetWMStateAbove) {
+        Layer::Above
+    } else if states.contains(&state.lib.xatom().NetWMStateBelow) {
+        Layer::Below
+    } else {
+        Layer::Normal
+    }
+}
+
+/* Managed windows of every monitor and workspace, in stacking order */
+pub fn update_client_list_stacking<W: WindowSystem>(state: &State<W>) {
+    let clients = state
+        .monitors
+        .values()
+        .flat_map(|mon| mon.get_client_keys())
+        .collect::<Vec<Window>>();
+    state.lib.update_net_client_list_stacking(&clients);
+}
+
+pub fn set_current_ws<W: WindowSystem>(state: &mut State<W>, ws: u32) -> Option<()> {
     let mon = match get_mon_by_ws(state, ws) {
         Some(mon) => state.monitors.get_mut(&mon)?,
         None => state.monitors.get_mut(&state.current_monitor)?,
@@ -115,7 +261,7 @@ pub fn set_current_ws(state: &mut State, ws: u32) -> Option<()> {
 
         let mon = state.monitors.get_mut(&state.current_monitor)?;
 
-        let mut new_ws = mon.remove_ws(mon.current_ws).expect("Should also be here");
+        let mut new_ws = mon.remove_ws(mon.current_ws)?;
         new_ws.clients.values_mut().for_each(|client| {
             client.handle_state.replace_with(|old| {
                 let mut handle_state = vec![HandleState::Unfocus];
@@ -129,7 +275,16 @@ pub fn set_current_ws(state: &mut State, ws: u32) -> Option<()> {
     }
 
 
-    let mut new_ws = mon.remove_ws(mon.current_ws).expect("Should also be here");
+    let mut new_ws = mon.remove_ws(mon.current_ws)?;
+    let sticky = new_ws
+        .clients
+        .values()
+        .filter(|client| client.is_sticky)
+        .map(|client| client.window())
+        .collect::<Vec<Window>>()
+        .into_iter()
+        .filter_map(|w| new_ws.remove_window(w))
+        .collect::<Vec<WindowWrapper>>();
     new_ws.clients.values_mut().for_each(|client| {
         client.handle_state.replace_with(|old| {
             let mut handle_state = vec![HandleState::Unmap, HandleState::Unfocus];
@@ -140,18 +295,27 @@ pub fn set_current_ws(state: &mut State, ws: u32) -> Option<()> {
     mon.add_ws(new_ws);
 
     if mon.contains_ws(ws) {
-        let mut new_ws = mon.remove_ws(ws).expect("Should also be here");
-        new_ws.clients.values_mut().for_each(|client| {
-            client.handle_state.replace_with(|old| {
-                let mut handle_state = vec![HandleState::Map];
-                old.append(&mut handle_state);
-                old.to_vec()
+        let mut new_ws = mon.remove_ws(ws)?;
+        new_ws
+            .clients
+            .values_mut()
+            .filter(|client| !client.is_minimized)
+            .for_each(|client| {
+                client.handle_state.replace_with(|old| {
+                    let mut handle_state = vec![HandleState::Map];
+                    old.append(&mut handle_state);
+                    old.to_vec()
+                });
             });
-        });
         mon.add_ws(new_ws);
     } else {
         mon.add_ws(Workspace::new(ws));
     }
+    if let Some(new_ws) = mon.workspaces.get_mut(&ws) {
+        sticky
+            .into_iter()
+            .for_each(|client| new_ws.add_window(client.window(), client));
+    }
 
     state.current_monitor = mon.id;
     if mon.workspaces.get(&mon.current_ws)?.clients.is_empty() {
@@ -166,11 +330,11 @@ pub fn set_current_ws(state: &mut State, ws: u32) -> Option<()> {
     Some(())
 }
 
-pub fn move_to_ws(state: &mut State, w: Window, ws: u32) -> Option<()> {
-    let ww = state
-        .monitors
-        .get_mut(&state.current_monitor)?
-        .remove_window(w)?;
+pub fn move_to_ws<W: WindowSystem>(state: &mut State<W>, w: Window, ws: u32) -> Option<()> {
+    let source = state.monitors.get_mut(&state.current_monitor)?;
+    let ww = source.remove_window(w)?;
+    // The weight was for the tiling w leaves, it starts out even where it goes
+    source.get_current_ws_mut()?.proportions.weights.remove(&w);
 
     let mon = match get_mon_by_ws(state, ws) {
         Some(mon) => state.monitors.get_mut(&mon)?,
@@ -181,18 +345,21 @@ pub fn move_to_ws(state: &mut State, w: Window, ws: u32) -> Option<()> {
         debug!("is current");
         let prev_ws = mon.current_ws;
         mon.current_ws = ws;
-        state.lib.unmap_window(w);
         let windows = mon.place_window(w);
         mon.add_window(w, ww.clone());
         windows.into_iter().for_each(|(win, rect)| {
-            let (new_win, new_ww) = mon.get_newest().clone().unwrap();
-            let new_win = new_win.clone();
-            let new_ww = new_ww.clone();
+            let (new_win, new_ww) = match mon.get_newest() {
+                Some((new_win, new_ww)) => (*new_win, new_ww.clone()),
+                None => return,
+            };
             mon.swap_window(win, |_mon, ww| WindowWrapper {
                 restore_position: rect.get_position(),
                 window_rect: rect,
                 previous_state: WindowState::Free,
                 current_state: WindowState::Free,
+                snap_zone: None,
+                is_floating: false,
+                is_sticky: false,
                 handle_state: vec![HandleState::Map].into(),
                 toc: if win == w { new_ww.toc.clone() } else { ww.toc },
                 ..ww
@@ -215,14 +382,18 @@ pub fn move_to_ws(state: &mut State, w: Window, ws: u32) -> Option<()> {
         mon.add_window(w, ww.clone());
         let windows = mon.place_window(w);
         windows.into_iter().for_each(|(win, rect)| {
-            let (new_win, new_ww) = mon.get_newest().clone().unwrap();
-            let new_win = new_win.clone();
-            let new_ww = new_ww.clone();
+            let (new_win, new_ww) = match mon.get_newest() {
+                Some((new_win, new_ww)) => (*new_win, new_ww.clone()),
+                None => return,
+            };
             mon.swap_window(win, |_mon, ww| WindowWrapper {
                 restore_position: rect.get_position(),
                 window_rect: rect,
                 previous_state: WindowState::Free,
                 current_state: WindowState::Free,
+                snap_zone: None,
+                is_floating: false,
+                is_sticky: false,
                 handle_state: vec![HandleState::Map].into(),
                 toc: if win == w { new_ww.toc.clone() } else { ww.toc },
                 ..ww
@@ -236,7 +407,7 @@ pub fn move_to_ws(state: &mut State, w: Window, ws: u32) -> Option<()> {
         });
 
         mon.current_ws = prev_ws;
-        state.lib.unmap_window(w);
+        unmap_window(state, w);
     } else {
         let mut new_ws = Workspace::new(ws);
         let windows = mon.place_window(w);
@@ -247,6 +418,9 @@ pub fn move_to_ws(state: &mut State, w: Window, ws: u32) -> Option<()> {
                 previous_state: WindowState::Free,
                 current_state: WindowState::Free,
                 window_rect: rect,
+                snap_zone: None,
+                is_floating: false,
+                is_sticky: false,
                 handle_state: HandleState::Map.into(),
                 ..ww
             };
@@ -254,20 +428,36 @@ pub fn move_to_ws(state: &mut State, w: Window, ws: u32) -> Option<()> {
         });
 
         mon.add_ws(new_ws);
-        state.lib.unmap_window(w);
+        unmap_window(state, w);
     }
 
     Some(())
 }
 
-pub fn reorder(state: &mut State) -> Option<()> {
-    let mon = state.monitors.get_mut(&state.current_monitor)?;
+/*
+ * Unmaps w and remembers that the UnmapNotify is hadlock's own, any other
+ * one of a client means it withdrew the window. Only mapped windows send one
+ */
+pub fn unmap_window<W: WindowSystem>(state: &State<W>, w: Window) {
+    if state.lib.is_viewable(w) {
+        *state.unmaps.borrow_mut().entry(w).or_insert(0) += 1;
+    }
+    state.lib.unmap_window(w);
+}
+
+pub fn reorder<W: WindowSystem>(state: &mut State<W>) -> Option<()> {
+    reorder_monitor(state, state.current_monitor)
+}
+
+pub fn reorder_monitor<W: WindowSystem>(state: &mut State<W>, mon_id: MonitorId) -> Option<()> {
+    let mon = state.monitors.get_mut(&mon_id)?;
     debug!("reorder focus: {}", state.focus_w);
 
     let windows = mon
         .get_current_ws()?
         .clients
         .values()
+        .filter(|x| x.tiles())
         .map(|x| x.clone())
         .collect::<Vec<WindowWrapper>>()
         .clone();
@@ -307,6 +497,7 @@ pub fn reorder(state: &mut State) -> Option<()> {
         mon.swap_window(win, |_mon, ww| WindowWrapper {
             window_rect: rect,
             current_state,
+            snap_zone: None,
             handle_state: handle_state.clone().into(),
             ..ww
         });
@@ -315,7 +506,272 @@ pub fn reorder(state: &mut State) -> Option<()> {
     Some(())
 }
 
-pub fn pointer_is_inside(state: &State, screen: &Screen) -> bool {
+/*
+ * Lifts w out of the tiling of a tiled workspace into a centered floating rect,
+ * or puts it back into the tiling order given by its toc
+ */
+pub fn toggle_floating<W: WindowSystem>(state: &mut State<W>, w: Window) -> Option<()> {
+    let mon = state.monitors.get_mut(&state.current_monitor)?;
+    if mon.get_current_layout()? == LayoutTag::Floating || mon.get_client(w)?.is_sticky {
+        return Some(());
+    }
+    let mon_id = mon.id;
+
+    if mon.get_client(w)?.is_floating {
+        mon.swap_window(w, |_mon, ww| WindowWrapper {
+            is_floating: false,
+            ..ww
+        })?;
+    } else {
+        let rect = float_rect(mon, w)?;
+        mon.swap_window(w, |_mon, ww| WindowWrapper {
+            window_rect: rect,
+            previous_state: ww.current_state,
+            current_state: WindowState::Free,
+            is_floating: true,
+            handle_state: vec![HandleState::Move, HandleState::Resize].into(),
+            ..ww
+        })?;
+    }
+    reorder_monitor(state, mon_id)
+}
+
+/*
+ * Sticky windows follow the current workspace of their monitor around and are kept
+ * out of the tiling like floating windows
+ */
+pub fn set_sticky<W: WindowSystem>(state: &mut State<W>, w: Window, sticky: bool) -> Option<()> {
+    let mon = state.monitors.get_mut(&get_mon_by_window(state, w)?)?;
+    let mon_id = mon.id;
+    let tiled = mon.get_current_layout()? != LayoutTag::Floating;
+    let ww = mon.get_client(w)?;
+    if ww.is_sticky == sticky {
+        return Some(());
+    }
+
+    let float = sticky && tiled && !ww.floats();
+    let rect = float_rect(mon, w)?;
+    let ww = mon.get_client_mut(w)?;
+    ww.is_sticky = sticky;
+    if float {
+        ww.set_window_state(WindowState::Free);
+        ww.window_rect = rect;
+        ww.handle_state
+            .borrow_mut()
+            .extend(vec![HandleState::Move, HandleState::Resize]);
+    } else {
+        ww.handle_state.borrow_mut().push(HandleState::Restack);
+    }
+
+    if tiled {
+        reorder_monitor(state, mon_id);
+    }
+    Some(())
+}
+
+/* Milliseconds a client gets to answer _NET_WM_PING */
+const PING_TIMEOUT: u64 = 3000;
+
+/* Asks w to close itself and pings it, see reducers::kill for the escalation */
+pub fn close_window<W: WindowSystem>(state: &mut State<W>, w: Window) -> Option<()> {
+    push_handle_state(state, w, HandleState::Destroy);
+    schedule_kill(state, w);
+    ping(state, w)
+}
+
+/*
+ * Kills w unless it is gone once CONFIG.close_timeout has passed. The serial
+ * tells a window that reused the id of a closed one apart, only one close is
+ * out per window
+ */
+fn schedule_kill<W: WindowSystem>(state: &mut State<W>, w: Window) {
+    if CONFIG.close_timeout == 0 || state.closes.contains_key(&w) {
+        return;
+    }
+    state.close_serial += 1;
+    let serial = state.close_serial;
+    state.closes.insert(w, serial);
+    state.tx.send_after(
+        std::time::Duration::from_millis(CONFIG.close_timeout),
+        InternalAction::Kill(w, serial),
+    );
+}
+
+/*
+ * Sends _NET_WM_PING to w if it takes it, w is marked as not responding
+ * unless it answers within PING_TIMEOUT. Only one ping is out per window
+ */
+pub fn ping<W: WindowSystem>(state: &mut State<W>, w: Window) -> Option<()> {
+    if state.pings.contains_key(&w) {
+        return Some(());
+    }
+    state.ping_serial += 1;
+    let serial = state.ping_serial;
+    if !state.lib.ping_client(w, serial) {
+        return None;
+    }
+    state.pings.insert(w, serial);
+
+    state.tx.send_after(
+        std::time::Duration::from_millis(PING_TIMEOUT),
+        InternalAction::PingTimeout(w, serial),
+    );
+    Some(())
+}
+
+pub fn pong<W: WindowSystem>(state: &mut State<W>, w: Window, serial: i64) -> Option<()> {
+    if state.pings.get(&w) != Some(&serial) {
+        return None;
+    }
+    state.pings.remove(&w);
+    set_responding(state, w, true)
+}
+
+/* None unless the responsiveness of w changed */
+pub fn set_responding<W: WindowSystem>(
+    state: &mut State<W>,
+    w: Window,
+    responding: bool,
+) -> Option<()> {
+    let mon = state.monitors.get_mut(&get_mon_by_window(state, w)?)?;
+    let ww = mon
+        .workspaces
+        .values_mut()
+        .find_map(|ws| ws.clients.get_mut(&w))?;
+    if ww.not_responding != responding {
+        return None;
+    }
+    ww.not_responding = !responding;
+    ww.handle_state.borrow_mut().push(HandleState::UpdateBorder);
+    Some(())
+}
+
+/* Unmaps w and keeps it out of the tiling until it is restored */
+pub fn minimize<W: WindowSystem>(state: &mut State<W>, w: Window) -> Option<()> {
+    let mon_id = get_mon_by_window(state, w)?;
+    let mon = state.monitors.get_mut(&mon_id)?;
+    let tiled = mon.get_current_layout()? != LayoutTag::Floating;
+    let ww = mon
+        .workspaces
+        .values_mut()
+        .find_map(|ws| ws.clients.get_mut(&w))?;
+    if ww.is_minimized {
+        return Some(());
+    }
+    ww.is_minimized = true;
+    ww.handle_state
+        .borrow_mut()
+        .extend(vec![HandleState::Unfocus, HandleState::Unmap]);
+
+    state.minimized.retain(|x| *x != w);
+    state.minimized.push(w);
+    if tiled {
+        reorder_monitor(state, mon_id);
+    }
+    if state.focus_w == w {
+        let _ = state.tx.send(InternalAction::Focus);
+    }
+    Some(())
+}
+
+/* Maps a minimized w again and switches to its workspace */
+pub fn restore<W: WindowSystem>(state: &mut State<W>, w: Window) -> Option<()> {
+    state.minimized.retain(|x| *x != w);
+    let mon_id = get_mon_by_window(state, w)?;
+    let ws = get_ws_by_window(state, w)?;
+    let ww = state
+        .monitors
+        .get_mut(&mon_id)?
+        .workspaces
+        .get_mut(&ws)?
+        .clients
+        .get_mut(&w)?;
+    if !ww.is_minimized {
+        return Some(());
+    }
+    ww.is_minimized = false;
+
+    let mon = state.monitors.get(&mon_id)?;
+    if mon_id != state.current_monitor || ws != mon.current_ws {
+        set_current_ws(state, ws)?;
+    }
+    if state.monitors.get(&mon_id)?.get_current_layout()? != LayoutTag::Floating {
+        reorder_monitor(state, mon_id);
+    }
+    push_handle_state(state, w, HandleState::Map);
+    let _ = state.tx.send(InternalAction::FocusSpecific(w));
+    Some(())
+}
+
+/* Centered rect a window gets when it is lifted out of the tiling */
+fn float_rect(mon: &Monitor, w: Window) -> Option<Rect> {
+    layout::layout_from_tag(LayoutTag::Floating)
+        .place_window(&mon.dock_area, &mon.screen, w, vec![], &Default::default())
+        .pop()
+        .map(|(_, rect)| rect)
+}
+
+/* Ends a move/resize started through _NET_WM_MOVERESIZE */
+pub fn end_client_drag<W: WindowSystem>(state: &mut State<W>) {
+    state.lib.ungrab_pointer();
+    if state.drag_keyboard {
+        state.lib.ungrab_keyboard();
+    }
+    state.drag_client = None;
+    state.drag_keyboard = false;
+    state.drag_corner = None;
+}
+
+/* Swaps the tiling order of two windows in the current workspace of mon */
+pub fn swap_toc(mon: &mut Monitor, a: Window, b: Window) -> Option<()> {
+    let toc_a = mon.get_client(a)?.toc;
+    let toc_b = mon.get_client(b)?.toc;
+    mon.swap_window(a, |_mon, ww| WindowWrapper { toc: toc_b, ..ww })?;
+    mon.swap_window(b, |_mon, ww| WindowWrapper { toc: toc_a, ..ww })
+}
+
+/* Tiled window at (x, y) on whatever monitor is there, except the window being dragged */
+pub fn tiled_window_at<W: WindowSystem>(
+    state: &State<W>,
+    dragged: Window,
+    x: i32,
+    y: i32,
+) -> Option<Window> {
+    let mon = state.monitors.get(&get_monitor_by_point(state, x, y))?;
+    if mon.get_current_layout()? == LayoutTag::Floating {
+        return None;
+    }
+    mon.get_current_ws()?
+        .clients
+        .values()
+        .filter(|ww| ww.window() != dragged && !ww.is_trans && ww.tiles())
+        .find(|ww| {
+            let (pos, size) = (ww.get_position(), ww.get_size());
+            x >= pos.x
+                && x < pos.x + size.width + 2 * CONFIG.border_width
+                && y >= pos.y
+                && y < pos.y + size.height + 2 * CONFIG.border_width
+        })
+        .map(|ww| ww.window())
+}
+
+/* Queues handle_state for w without dropping what is already queued */
+pub fn push_handle_state<W: WindowSystem>(
+    state: &State<W>,
+    w: Window,
+    handle_state: HandleState,
+) -> Option<()> {
+    let mon = state.monitors.get(&get_mon_by_window(state, w)?)?;
+    mon.workspaces
+        .values()
+        .find_map(|ws| ws.clients.get(&w))?
+        .handle_state
+        .borrow_mut()
+        .push(handle_state);
+    Some(())
+}
+
+pub fn pointer_is_inside<W: WindowSystem>(state: &State<W>, screen: &Screen) -> bool {
     let pointer_pos = state.lib.pointer_pos(state.focus_w);
     //debug!("pointer pos: {:?}", pointer_pos);
     let inside_height =
@@ -326,7 +782,12 @@ pub fn pointer_is_inside(state: &State, screen: &Screen) -> bool {
     inside_height && inside_width
 }
 
-pub fn point_is_inside(_state: &State, screen: &Screen, x: i32, y: i32) -> bool {
+pub fn point_is_inside<W: WindowSystem>(
+    _state: &State<W>,
+    screen: &Screen,
+    x: i32,
+    y: i32,
+) -> bool {
     let inside_height = y >= screen.y && y <= screen.y + screen.height as i32;
 
     let inside_width = x >= screen.x && x <= screen.x + screen.width as i32;
@@ -334,7 +795,7 @@ pub fn point_is_inside(_state: &State, screen: &Screen, x: i32, y: i32) -> bool
     inside_height && inside_width
 }
 
-pub fn get_monitor_by_mouse(state: &State) -> MonitorId {
+pub fn get_monitor_by_mouse<W: WindowSystem>(state: &State<W>) -> MonitorId {
     let mon_vec = state
         .monitors
         .iter()
@@ -347,7 +808,7 @@ pub fn get_monitor_by_mouse(state: &State) -> MonitorId {
     }
 }
 
-pub fn get_monitor_by_point(state: &State, x: i32, y: i32) -> MonitorId {
+pub fn get_monitor_by_point<W: WindowSystem>(state: &State<W>, x: i32, y: i32) -> MonitorId {
     let mon_vec = state
         .monitors
         .iter()
@@ -359,3 +820,323 @@ pub fn get_monitor_by_point(state: &State, x: i32, y: i32) -> MonitorId {
         None => state.current_monitor,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        hdl_reactor::HdlReactor,
+        models::dockarea::DockArea,
+        models::internal_action::{self, ActionReceiver},
+        session::Session,
+        xlibwrapper::{action, fake::*},
+    };
+    use reducer::{Reactor, Reducer};
+    use std::rc::Rc;
+
+    struct Harness {
+        lib: Rc<FakeWindowSystem>,
+        state: State<FakeWindowSystem>,
+        reactor: HdlReactor<FakeWindowSystem>,
+        _rx: ActionReceiver,
+    }
+
+    impl Harness {
+        fn new(layout: LayoutTag) -> Self {
+            let lib = Rc::new(FakeWindowSystem::with_screen(1000, 500));
+            let (tx, rx) = internal_action::channel().expect("channel");
+            let mut state = State::new(lib.clone(), tx.clone());
+            let ws = state
+                .monitors
+                .get_mut(&0)
+                .and_then(|mon| mon.get_current_ws_mut())
+                .expect("workspace");
+            while ws.get_current_layout() != layout {
+                ws.circulate_layout();
+            }
+            let reactor = HdlReactor::new(lib.clone(), tx);
+            Self {
+                lib,
+                state,
+                reactor,
+                _rx: rx,
+            }
+        }
+
+        fn map(&mut self) -> Window {
+            let w = self.lib.add_window(FakeWindow::default());
+            let parent = self.lib.get_root();
+            self.state.reduce(action::MapRequest { win: w, parent });
+            self.react();
+            w
+        }
+
+        fn react(&self) {
+            self.reactor.react(&self.state);
+        }
+
+        fn geometry(&self, w: Window) -> Geometry {
+            self.lib.get_geometry(w)
+        }
+    }
+
+    fn overlaps(a: Geometry, b: Geometry) -> bool {
+        a.x < b.x + b.width as i32
+            && b.x < a.x + a.width as i32
+            && a.y < b.y + b.height as i32
+            && b.y < a.y + a.height as i32
+    }
+
+    #[test]
+    fn tiled_windows_share_the_screen() {
+        let mut harness = Harness::new(LayoutTag::ColumnMaster);
+        let first = harness.map();
+        assert_eq!(harness.geometry(first).width, 1000);
+
+        let second = harness.map();
+        let (a, b) = (harness.geometry(first), harness.geometry(second));
+        assert!(!overlaps(a, b), "{:?} overlaps {:?}", a, b);
+        assert!(harness.lib.window(first).is_some_and(|w| w.mapped));
+        assert!(harness.lib.window(second).is_some_and(|w| w.mapped));
+    }
+
+    #[test]
+    fn minimized_window_leaves_the_tiling() {
+        let mut harness = Harness::new(LayoutTag::ColumnMaster);
+        let first = harness.map();
+        let second = harness.map();
+        let tiled_width = harness.geometry(first).width;
+
+        harness.state.reduce(action::Focus { win: second });
+        minimize(&mut harness.state, second);
+        harness.react();
+        assert!(harness.lib.window(second).is_some_and(|w| !w.mapped));
+        assert!(harness.geometry(first).width > tiled_width);
+
+        restore(&mut harness.state, second);
+        harness.react();
+        assert!(harness.lib.window(second).is_some_and(|w| w.mapped));
+        assert_eq!(harness.geometry(first).width, tiled_width);
+    }
+
+    #[test]
+    fn switching_workspace_maps_only_its_windows() {
+        let mut harness = Harness::new(LayoutTag::Floating);
+        let w = harness.map();
+
+        set_current_ws(&mut harness.state, 1);
+        harness.react();
+        assert!(harness.lib.window(w).is_some_and(|w| !w.mapped));
+
+        set_current_ws(&mut harness.state, 0);
+        harness.react();
+        assert!(harness.lib.window(w).is_some_and(|w| w.mapped));
+    }
+
+    #[test]
+    fn only_minimized_windows_are_hidden() {
+        let mut harness = Harness::new(LayoutTag::Floating);
+        let w = harness.map();
+        let hidden = harness.lib.xatom().NetWMStateHidden;
+        let is_hidden = |harness: &Harness| {
+            harness.lib.window(w).is_some_and(|w| w.states.contains(&hidden))
+        };
+
+        set_current_ws(&mut harness.state, 1);
+        harness.react();
+        assert!(!is_hidden(&harness));
+
+        set_current_ws(&mut harness.state, 0);
+        harness.react();
+        minimize(&mut harness.state, w);
+        harness.react();
+        assert!(is_hidden(&harness));
+
+        restore(&mut harness.state, w);
+        harness.react();
+        assert!(!is_hidden(&harness));
+    }
+
+    #[test]
+    fn window_unmapped_by_its_client_leaves_the_tiling() {
+        let mut harness = Harness::new(LayoutTag::ColumnMaster);
+        let first = harness.map();
+        let second = harness.map();
+        let tiled_width = harness.geometry(first).width;
+
+        // The unmaps of a workspace switch are hadlock's own
+        set_current_ws(&mut harness.state, 1);
+        harness.react();
+        for w in [first, second].iter() {
+            harness.state.reduce(action::UnmapNotify { win: *w });
+        }
+        set_current_ws(&mut harness.state, 0);
+        harness.react();
+        assert!(get_mon_by_window(&harness.state, first).is_some());
+        assert!(get_mon_by_window(&harness.state, second).is_some());
+
+        harness.state.reduce(action::Focus { win: first });
+        harness.lib.unmap_window(second);
+        harness.state.reduce(action::UnmapNotify { win: second });
+        harness.react();
+        assert_eq!(get_mon_by_window(&harness.state, second), None);
+        assert!(harness.geometry(first).width > tiled_width);
+    }
+
+    #[test]
+    fn destroyed_window_on_hidden_workspace_is_forgotten() {
+        let mut harness = Harness::new(LayoutTag::Floating);
+        let w = harness.map();
+        set_current_ws(&mut harness.state, 1);
+        harness.react();
+
+        harness.state.reduce(action::Destroy { win: w });
+        assert_eq!(get_mon_by_window(&harness.state, w), None);
+
+        // Events for the window still in the queue are ignored
+        harness.state.reduce(action::ConfigurationRequest {
+            win: w,
+            win_changes: WindowChanges::default(),
+            value_mask: 0,
+            parent: harness.lib.get_root(),
+        });
+        harness.state.reduce(action::Focus { win: w });
+        assert_ne!(harness.state.focus_w, w);
+    }
+
+    #[test]
+    fn moved_window_leaves_its_weight_behind() {
+        let mut harness = Harness::new(LayoutTag::ColumnMaster);
+        let _master = harness.map();
+        let first = harness.map();
+        let second = harness.map();
+        let weights = |harness: &Harness, tag: u32| {
+            harness
+                .state
+                .monitors
+                .get(&0)
+                .and_then(|mon| mon.workspaces.get(&tag))
+                .map(|ws| ws.proportions.weights.clone())
+                .unwrap_or_default()
+        };
+        {
+            let ws = harness
+                .state
+                .monitors
+                .get_mut(&0)
+                .and_then(|mon| mon.get_current_ws_mut())
+                .expect("workspace");
+            ws.proportions.weights.insert(first, 1.5);
+            ws.proportions.weights.insert(second, 0.5);
+        }
+
+        move_to_ws(&mut harness.state, first, 1);
+        assert!(!weights(&harness, 0).contains_key(&first));
+        assert!(weights(&harness, 0).contains_key(&second));
+        assert!(!weights(&harness, 1).contains_key(&first));
+    }
+
+    #[test]
+    fn restart_puts_windows_back_on_their_workspaces() {
+        let mut harness = Harness::new(LayoutTag::ColumnMaster);
+        let first = harness.map();
+        let second = harness.map();
+        let hidden = harness.map();
+        move_to_ws(&mut harness.state, hidden, 1);
+        reorder(&mut harness.state);
+        harness.react();
+        let geometries = (harness.geometry(first), harness.geometry(second));
+
+        let session = serde_json::to_string(&Session::from_state(&harness.state))
+            .and_then(|session| serde_json::from_str::<Session>(&session))
+            .expect("session round trip");
+        let mut restarted = Harness::new(LayoutTag::Floating);
+        for w in [first, second, hidden].iter() {
+            restarted.lib.insert_window(*w, FakeWindow::default());
+        }
+        restarted.state.reduce(action::RestoreSession { session });
+        restarted.react();
+
+        let mon = restarted.state.monitors.get(&0).expect("monitor");
+        assert_eq!(mon.get_current_layout(), Some(LayoutTag::ColumnMaster));
+        assert_eq!(mon.get_current_windows(), vec![first, second]);
+        assert!(mon.workspaces.get(&1).is_some_and(|ws| ws.contains_window(hidden)));
+        assert_eq!((restarted.geometry(first), restarted.geometry(second)), geometries);
+        assert!(restarted.lib.window(hidden).is_some_and(|w| !w.mapped));
+    }
+
+    #[test]
+    fn destroyed_dock_is_not_restacked() {
+        let mut harness = Harness::new(LayoutTag::Floating);
+        let dock = harness.lib.add_window(FakeWindow {
+            window_type: WindowType::Dock,
+            strut: Some(DockArea::default()),
+            ..FakeWindow::default()
+        });
+        let parent = harness.lib.get_root();
+        harness.state.reduce(action::MapRequest { win: dock, parent });
+        harness.state.reduce(action::MapRequest { win: dock, parent });
+        assert_eq!(harness.state.dock_windows, vec![dock]);
+
+        harness.state.reduce(action::DestroyNotify { win: dock });
+        harness.lib.clear_calls();
+        let w = harness.map();
+        raise_window(&harness.state, w);
+        let restacks: Vec<Call> = harness
+            .lib
+            .calls()
+            .into_iter()
+            .filter(|call| matches!(call, Call::Restack(_)))
+            .collect();
+        assert!(!restacks.is_empty());
+        assert!(restacks.iter().all(|call| *call == Call::Restack(vec![w])), "{:?}", restacks);
+    }
+
+    #[test]
+    fn kill_spares_a_window_that_reused_the_id() {
+        let mut harness = Harness::new(LayoutTag::Floating);
+        let w = harness.map();
+        close_window(&mut harness.state, w);
+        harness.react();
+        let serial = *harness.state.closes.get(&w).expect("close scheduled");
+
+        // The client closes in time and a new window gets its id
+        harness.state.reduce(action::DestroyNotify { win: w });
+        harness.lib.insert_window(w, FakeWindow::default());
+        let parent = harness.lib.get_root();
+        harness.state.reduce(action::MapRequest { win: w, parent });
+        harness.react();
+
+        harness.lib.clear_calls();
+        harness.state.reduce(action::Kill { win: w, serial });
+        harness.react();
+        assert!(!harness.lib.calls().contains(&Call::Kill(w)));
+
+        close_window(&mut harness.state, w);
+        let serial = *harness.state.closes.get(&w).expect("close scheduled");
+        harness.state.reduce(action::Kill { win: w, serial });
+        harness.react();
+        assert!(harness.lib.calls().contains(&Call::Kill(w)));
+    }
+
+    #[test]
+    fn focus_moves_between_windows() {
+        let mut harness = Harness::new(LayoutTag::Floating);
+        let first = harness.map();
+        let second = harness.map();
+
+        harness.state.reduce(action::Focus { win: first });
+        harness.react();
+        assert_eq!(harness.lib.focused(), first);
+
+        harness.lib.clear_calls();
+        harness.state.reduce(action::Focus { win: second });
+        harness.react();
+        assert_eq!(harness.state.focus_w, second);
+        assert_eq!(harness.lib.focused(), second);
+        assert!(harness
+            .lib
+            .calls()
+            .contains(&Call::BorderColor(first, CONFIG.background_color.value())));
+    }
+}
@@ -1,11 +1,15 @@
 #![allow(unused_variables, dead_code)]
 
 use super::xlibmodels::*;
+use crate::session::Session;
+use serde::{Deserialize, Serialize};
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ConfigurationNotification {
     pub win: Window,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ConfigurationRequest {
     pub win: Window,
     pub win_changes: WindowChanges,
@@ -13,21 +17,25 @@ pub struct ConfigurationRequest {
     pub parent: Window,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ClientMessageRequest {
     pub win: Window,
     pub message_type: u64,
     pub data: Vec<i64>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MapRequest {
     pub win: Window,
     pub parent: Window,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UnmapNotify {
     pub win: Window,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ButtonPress {
     pub win: Window,
     pub sub_win: Window,
@@ -37,6 +45,7 @@ pub struct ButtonPress {
     pub state: u32,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ButtonRelease {
     pub win: Window,
     pub sub_win: Window,
@@ -46,18 +55,21 @@ pub struct ButtonRelease {
     pub state: u32,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyPress {
     pub win: Window,
     pub state: u32,
     pub keycode: u32,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyRelease {
     pub win: Window,
     pub state: u32,
     pub keycode: u32,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MotionNotify {
     pub win: Window,
     pub sub_win: Window,
@@ -66,34 +78,73 @@ pub struct MotionNotify {
     pub state: u32,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EnterNotify {
     pub win: Window,
     pub sub_win: Window,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LeaveNotify {
     pub win: Window,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PropertyNotify {
     pub win: Window,
     pub atom: u64,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Expose {
     pub win: Window,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Destroy {
     pub win: Window,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DestroyNotify {
+    pub win: Window,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Kill {
+    pub win: Window,
+    pub serial: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PingFocused;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PingTimeout {
+    pub win: Window,
+    pub serial: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Focus {
     pub win: Window,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Restore {
+    pub win: Window,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RestoreSession {
+    pub session: Session,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UpdateLayout;
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ButtonReleased;
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UnknownEvent;
@@ -1,7 +1,12 @@
 #![allow(unused_variables, deprecated, dead_code)]
 use std::ffi::CString;
 use std::mem::{self, MaybeUninit};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use std::os::raw::*;
+use std::os::unix::io::RawFd;
 pub use x11_dl::xlib;
 
 use super::{masks::*, util::*, xatom::*, xlibmodels::*};
@@ -9,42 +14,57 @@ use super::{masks::*, util::*, xatom::*, xlibmodels::*};
 use super::cursor::Cursor;
 use super::util::Position;
 use crate::config::*;
+use crate::HadlockResult;
+use crate::error::{self, HadlockError};
 
-use crate::models::{dockarea::DockArea, screen::Screen, window_type::WindowType};
+use crate::models::{
+    dockarea::DockArea, rect::Rect, screen::Screen, window_type::WindowType, Corner,
+};
 
+/* Xlib ignores the return value, errors are only reported */
 pub(crate) unsafe extern "C" fn error_handler(
     _: *mut xlib::Display,
     e: *mut xlib::XErrorEvent,
 ) -> i32 {
-    let err = *e;
-    if err.error_code == xlib::BadWindow {
-        return 0;
-    }
-    1
+    error::report("X request", Err(HadlockError::from(&*e)));
+    0
 }
 
+/* Set by on_wm_detected, only one client at a time can redirect root */
+static OTHER_WM: AtomicBool = AtomicBool::new(false);
+
 pub(crate) unsafe extern "C" fn on_wm_detected(
     _: *mut xlib::Display,
     e: *mut xlib::XErrorEvent,
 ) -> i32 {
     if (*e).error_code == xlib::BadAccess {
-        error!("Other wm registered!");
-        return 1;
+        OTHER_WM.store(true, Ordering::SeqCst);
     }
     0
 }
 
+/* How long a window manager that is replaced gets to let go of the screen */
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(15);
+
+const NORMAL_STATE: c_ulong = 1;
+pub const ICONIC_STATE: c_ulong = 3;
+
 pub struct XlibWrapper {
     lib: xlib::Xlib,
     pub xatom: XAtom,
     display: *mut Display,
     root: Window,
     cursors: Cursor,
+    wm_selection: xlib::Atom,
 }
 
 impl XlibWrapper {
-    pub fn new() -> Self {
-        let (disp, root, lib, xatom, cursors) = unsafe {
+    /*
+     * Connects and becomes the window manager of the default screen. Fails
+     * when another one is running, unless replace asks it to step down
+     */
+    pub fn new(replace: bool) -> HadlockResult<Self> {
+        let (disp, root, lib, xatom, cursors, wm_selection) = unsafe {
             let lib = xlib::Xlib::open().expect("xlibwrapper::core: new");
             let disp = (lib.XOpenDisplay)(std::ptr::null_mut());
 
@@ -53,8 +73,6 @@ impl XlibWrapper {
             }
 
             let root = (lib.XDefaultRootWindow)(disp);
-            (lib.XSetErrorHandler)(Some(on_wm_detected));
-            (lib.XSync)(disp, 0);
             (lib.XSetErrorHandler)(Some(error_handler));
             let xatom = XAtom::new(&lib, disp);
 
@@ -62,8 +80,10 @@ impl XlibWrapper {
             let display_width = (lib.XDisplayWidth)(disp, screen_id);
             let display_height = (lib.XDisplayHeight)(disp, screen_id);
             let cursors = Cursor::new(&lib, disp);
+            let name = CString::new(format!("WM_S{}", screen_id))?;
+            let wm_selection = (lib.XInternAtom)(disp, name.as_ptr(), xlib::False);
 
-            (disp, root, lib, xatom, cursors)
+            (disp, root, lib, xatom, cursors, wm_selection)
         };
 
         let mut ret = Self {
@@ -72,10 +92,139 @@ impl XlibWrapper {
             display: disp,
             root,
             cursors,
+            wm_selection,
         };
+        ret.acquire_wm_selection(replace)?;
+        ret.redirect_root()?;
         ret.init();
         ret.init_desktops_hints();
-        ret
+        Ok(ret)
+    }
+
+    /*
+     * ICCCM manager selection: owning WM_Sn makes hadlock the window manager
+     * of screen n. A previous owner is asked to leave by taking the selection
+     * over and is waited for until it destroyed its owner window
+     */
+    fn acquire_wm_selection(&self, replace: bool) -> HadlockResult<()> {
+        let owner = unsafe {
+            (self.lib.XCreateSimpleWindow)(self.display, self.root, -1, -1, 1, 1, 0, 0, 0)
+        };
+        let time = self.server_time(owner);
+
+        self.grab_server();
+        let previous = unsafe { (self.lib.XGetSelectionOwner)(self.display, self.wm_selection) };
+        if previous != 0 {
+            if !replace {
+                self.ungrab_server();
+                return Err("another window manager is running, start with --replace to take over".into());
+            }
+            self.select_input(previous, xlib::StructureNotifyMask);
+        }
+        self.ungrab_server();
+
+        unsafe {
+            (self.lib.XSetSelectionOwner)(self.display, self.wm_selection, owner, time);
+            if (self.lib.XGetSelectionOwner)(self.display, self.wm_selection) != owner {
+                return Err("failed to acquire the window manager selection".into());
+            }
+        }
+        if previous != 0 {
+            info!("Waiting for the running window manager to exit");
+            self.wait_for_destroy(previous)?;
+        }
+
+        let mut event = xlib::XClientMessageEvent {
+            type_: xlib::ClientMessage,
+            serial: 0,
+            send_event: xlib::True,
+            display: self.display,
+            window: self.root,
+            message_type: self.get_atom("MANAGER"),
+            format: 32,
+            data: xlib::ClientMessageData::new(),
+        };
+        event.data.set_long(0, time as c_long);
+        event.data.set_long(1, self.wm_selection as c_long);
+        event.data.set_long(2, owner as c_long);
+        let mut event = xlib::XEvent::from(event);
+        unsafe {
+            (self.lib.XSendEvent)(
+                self.display,
+                self.root,
+                xlib::False,
+                xlib::StructureNotifyMask,
+                &mut event,
+            );
+        }
+        self.sync(false);
+        Ok(())
+    }
+
+    /* A timestamp from the server, read off the PropertyNotify of an empty append to w */
+    fn server_time(&self, w: Window) -> xlib::Time {
+        self.select_input(w, xlib::PropertyChangeMask);
+        unsafe {
+            (self.lib.XChangeProperty)(
+                self.display,
+                w,
+                xlib::XA_WM_NAME,
+                xlib::XA_STRING,
+                8,
+                xlib::PropModeAppend,
+                ptr::null(),
+                0,
+            );
+            let mut event: xlib::XEvent = mem::zeroed();
+            (self.lib.XWindowEvent)(self.display, w, xlib::PropertyChangeMask, &mut event);
+            self.select_input(w, xlib::NoEventMask);
+            xlib::XPropertyEvent::from(event).time
+        }
+    }
+
+    fn wait_for_destroy(&self, w: Window) -> HadlockResult<()> {
+        let start = Instant::now();
+        loop {
+            unsafe {
+                let mut event: xlib::XEvent = mem::zeroed();
+                while (self.lib.XCheckWindowEvent)(
+                    self.display,
+                    w,
+                    xlib::StructureNotifyMask,
+                    &mut event,
+                ) == xlib::True
+                {
+                    if event.get_type() == xlib::DestroyNotify {
+                        return Ok(());
+                    }
+                }
+            }
+            if start.elapsed() > REPLACE_TIMEOUT {
+                return Err("the running window manager did not exit".into());
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    /* Selecting SubstructureRedirect fails while a window manager without WM_Sn runs */
+    fn redirect_root(&self) -> HadlockResult<()> {
+        OTHER_WM.store(false, Ordering::SeqCst);
+        unsafe {
+            (self.lib.XSetErrorHandler)(Some(on_wm_detected));
+            self.select_input(self.root, xlib::SubstructureRedirectMask);
+            (self.lib.XSync)(self.display, xlib::False);
+            (self.lib.XSetErrorHandler)(Some(error_handler));
+        }
+        if OTHER_WM.load(Ordering::SeqCst) {
+            return Err("another window manager is running".into());
+        }
+        Ok(())
+    }
+
+    /* Another window manager took WM_Sn over, hadlock has to let go of the screen */
+    pub fn lost_wm_selection(&self, event: &xlib::XEvent) -> bool {
+        event.get_type() == xlib::SelectionClear
+            && xlib::XSelectionClearEvent::from(*event).selection == self.wm_selection
     }
 
     pub fn get_screens(&self) -> Vec<Screen> {
@@ -170,8 +319,8 @@ impl XlibWrapper {
             (self.lib.XUngrabKey)(self.display, xlib::AnyKey, xlib::AnyModifier, self.root);
             (self.lib.XDeleteProperty)(self.display, self.root, self.xatom.NetClientList);
             let keys = vec![
-                "Left", "Right", "Up", "Down", "Return", "q", "d", "e", "l", "r", "1", "2", "3",
-                "4", "5", "6", "7", "8", "9",
+                "Left", "Right", "Up", "Down", "Return", "q", "d", "e", "l", "n", "r", "1", "2",
+                "3", "4", "5", "6", "7", "8", "9",
             ];
 
             let _ = keys
@@ -365,19 +514,91 @@ impl XlibWrapper {
     }
 
     pub fn set_window_states_atoms(&self, window: xlib::Window, states: Vec<xlib::Atom>) {
-        let data: Vec<u32> = states.iter().map(|x| *x as u32).collect();
+        self.set_window_prop(window, self.xatom.NetWMState, xlib::XA_ATOM, &states);
+    }
+
+    /* ICCCM WM_STATE, NormalState while the client is shown and IconicState while it is not */
+    pub fn set_wm_state(&self, window: xlib::Window, normal: bool) {
+        let state = if normal { NORMAL_STATE } else { ICONIC_STATE };
+        let data = vec![state, 0];
+        self.set_window_prop(window, self.xatom.WMState, self.xatom.WMState, &data);
+    }
+
+    pub fn set_net_wm_desktop(&self, window: xlib::Window, desktop: u32) {
+        let data = vec![desktop as c_ulong];
+        self.set_window_prop(window, self.xatom.NetWMDesktop, xlib::XA_CARDINAL, &data);
+    }
+
+    /* The desktop a client is on, set by hadlock or the window manager before it */
+    pub fn get_net_wm_desktop(&self, window: xlib::Window) -> Option<u32> {
+        let mut format_return: i32 = 0;
+        let mut nitems_return: c_ulong = 0;
+        let mut bytes_after_return: c_ulong = 0;
+        let mut type_return: xlib::Atom = 0;
+        let mut prop_return: *mut c_uchar = ptr::null_mut();
+        unsafe {
+            let status = (self.lib.XGetWindowProperty)(
+                self.display,
+                window,
+                self.xatom.NetWMDesktop,
+                0,
+                1,
+                xlib::False,
+                xlib::XA_CARDINAL,
+                &mut type_return,
+                &mut format_return,
+                &mut nitems_return,
+                &mut bytes_after_return,
+                &mut prop_return,
+            );
+            if status != i32::from(xlib::Success) || prop_return.is_null() {
+                return None;
+            }
+            #[allow(clippy::cast_ptr_alignment)]
+            let desktop = if format_return == 32 && nitems_return == 1 {
+                Some(*(prop_return as *const c_ulong) as u32)
+            } else {
+                None
+            };
+            (self.lib.XFree)(prop_return as *mut c_void);
+            desktop
+        }
+    }
+
+    /* Widths of the left, right, top and bottom frame around the client */
+    pub fn set_frame_extents(
+        &self,
+        window: xlib::Window,
+        left: i32,
+        right: i32,
+        top: i32,
+        bottom: i32,
+    ) {
+        let data = vec![left, right, top, bottom]
+            .into_iter()
+            .map(|x| x.max(0) as c_ulong)
+            .collect::<Vec<c_ulong>>();
+        self.set_window_prop(window, self.xatom.NetFrameExtents, xlib::XA_CARDINAL, &data);
+    }
+
+    fn set_window_prop(
+        &self,
+        window: xlib::Window,
+        atom: xlib::Atom,
+        type_: xlib::Atom,
+        data: &[c_ulong],
+    ) {
         unsafe {
             (self.lib.XChangeProperty)(
                 self.display,
                 window,
-                self.xatom.NetWMState,
-                xlib::XA_ATOM,
+                atom,
+                type_,
                 32,
                 xlib::PropModeReplace,
                 data.as_ptr() as *const u8,
                 data.len() as i32,
             );
-            std::mem::forget(data);
         }
     }
 
@@ -658,21 +879,71 @@ impl XlibWrapper {
 
     pub fn update_net_client_list(&self, clients: Vec<Window>) {
         unsafe {
-            (self.lib.XDeleteProperty)(self.display, self.root, self.xatom.NetClientList);
-            clients.iter().for_each(|c| {
-                let list = vec![c];
-                (self.lib.XChangeProperty)(
-                    self.display,
-                    self.root,
-                    self.xatom.NetClientList,
-                    xlib::XA_WINDOW,
-                    32,
-                    xlib::PropModeAppend,
-                    list.as_ptr() as *const u8,
-                    1,
-                );
-                mem::forget(list);
+            (self.lib.XChangeProperty)(
+                self.display,
+                self.root,
+                self.xatom.NetClientList,
+                xlib::XA_WINDOW,
+                32,
+                xlib::PropModeReplace,
+                clients.as_ptr() as *const u8,
+                clients.len() as i32,
+            );
+        }
+    }
+
+    /* Sets _NET_CLIENT_LIST_STACKING to clients in the bottom to top order of the X stack */
+    pub fn update_net_client_list_stacking(&self, clients: &[Window]) {
+        let stacking = self
+            .get_top_level_windows()
+            .into_iter()
+            .filter(|w| clients.contains(w))
+            .collect::<Vec<Window>>();
+        unsafe {
+            (self.lib.XChangeProperty)(
+                self.display,
+                self.root,
+                self.xatom.NetClientListStacking,
+                xlib::XA_WINDOW,
+                32,
+                xlib::PropModeReplace,
+                stacking.as_ptr() as *const u8,
+                stacking.len() as i32,
+            );
+        }
+    }
+
+    /* One x, y, width, height rect per desktop */
+    pub fn update_net_workarea(&self, work_areas: &[Rect]) {
+        let data = work_areas
+            .iter()
+            .flat_map(|rect| {
+                let (pos, size) = (rect.get_position(), rect.get_size());
+                vec![pos.x, pos.y, size.width, size.height]
             })
+            .map(c_long::from)
+            .collect::<Vec<c_long>>();
+        self.set_desktop_prop_long(&data, self.xatom.NetWorkarea);
+    }
+
+    pub fn update_net_desktop_geometry(&self, size: Size) {
+        let data = vec![size.width as c_long, size.height as c_long];
+        self.set_desktop_prop_long(&data, self.xatom.NetDesktopGeometry);
+    }
+
+    /* Format 32 properties are read from an array of longs by Xlib */
+    fn set_desktop_prop_long(&self, data: &[c_long], atom: c_ulong) {
+        unsafe {
+            (self.lib.XChangeProperty)(
+                self.display,
+                self.root,
+                atom,
+                xlib::XA_CARDINAL,
+                32,
+                xlib::PropModeReplace,
+                data.as_ptr() as *const u8,
+                data.len() as i32,
+            );
         }
     }
 
@@ -732,6 +1003,10 @@ impl XlibWrapper {
         }
     }
 
+    pub fn is_viewable(&self, w: Window) -> bool {
+        self.get_window_attributes(w).map_state == xlib::IsViewable
+    }
+
     pub fn grab_server(&self) {
         unsafe {
             (self.lib.XGrabServer)(self.display);
@@ -784,6 +1059,87 @@ impl XlibWrapper {
         }
     }
 
+    pub fn set_move_cursor(&self) {
+        self.change_pointer_grab_cursor(self.cursors.move_cursor);
+    }
+
+    pub fn set_resize_cursor(&self, corner: Corner) {
+        self.change_pointer_grab_cursor(self.resize_cursor(corner));
+    }
+
+    fn resize_cursor(&self, corner: Corner) -> u64 {
+        match corner {
+            Corner::NorthWest => self.cursors.resize_nw_cursor,
+            Corner::North => self.cursors.resize_n_cursor,
+            Corner::NorthEast => self.cursors.resize_ne_cursor,
+            Corner::East => self.cursors.resize_e_cursor,
+            Corner::SouthEast => self.cursors.resize_se_cursor,
+            Corner::South => self.cursors.resize_s_cursor,
+            Corner::SouthWest => self.cursors.resize_sw_cursor,
+            Corner::West => self.cursors.resize_w_cursor,
+        }
+    }
+
+    /*
+     * Actively grabs the pointer on root for a move (None) or a resize from corner,
+     * the events of the drag are then reported on root
+     */
+    pub fn grab_pointer(&self, corner: Option<Corner>) -> bool {
+        let cursor = match corner {
+            Some(corner) => self.resize_cursor(corner),
+            None => self.cursors.move_cursor,
+        };
+        unsafe {
+            (self.lib.XGrabPointer)(
+                self.display,
+                self.root,
+                xlib::False,
+                (ButtonPressMask | ButtonReleaseMask | ButtonMotionMask) as u32,
+                GrabModeAsync,
+                GrabModeAsync,
+                0,
+                cursor,
+                CurrentTime,
+            ) == xlib::GrabSuccess
+        }
+    }
+
+    pub fn ungrab_pointer(&self) {
+        unsafe {
+            (self.lib.XUngrabPointer)(self.display, CurrentTime);
+        }
+    }
+
+    pub fn grab_keyboard(&self) -> bool {
+        unsafe {
+            (self.lib.XGrabKeyboard)(
+                self.display,
+                self.root,
+                xlib::False,
+                GrabModeAsync,
+                GrabModeAsync,
+                CurrentTime,
+            ) == xlib::GrabSuccess
+        }
+    }
+
+    pub fn ungrab_keyboard(&self) {
+        unsafe {
+            (self.lib.XUngrabKeyboard)(self.display, CurrentTime);
+        }
+    }
+
+    fn change_pointer_grab_cursor(&self, cursor: u64) {
+        unsafe {
+            (self.lib.XChangeActivePointerGrab)(
+                self.display,
+                (ButtonPressMask | ButtonReleaseMask | ButtonMotionMask) as u32,
+                cursor,
+                CurrentTime,
+            );
+        }
+    }
+
     pub fn str_to_keycode(&self, key: &str) -> Option<KeyCode> {
         match keysym_lookup::into_keysym(key) {
             Some(key) => Some(self.key_sym_to_keycode(key.into())),
@@ -839,6 +1195,19 @@ impl XlibWrapper {
         }
     }
 
+    /* WM_NAME of w, lossy if it is not valid utf-8 */
+    pub fn get_window_title(&self, w: Window) -> Option<String> {
+        unsafe {
+            let mut name: *mut c_char = std::ptr::null_mut();
+            if (self.lib.XFetchName)(self.display, w, &mut name) == 0 || name.is_null() {
+                return None;
+            }
+            let title = std::ffi::CStr::from_ptr(name).to_string_lossy().into_owned();
+            (self.lib.XFree)(name as *mut c_void);
+            Some(title)
+        }
+    }
+
     pub fn get_atom_prop_value(
         &self,
         window: xlib::Window,
@@ -918,15 +1287,40 @@ impl XlibWrapper {
         }
     }
 
+    pub fn supports_ping(&self, w: Window) -> bool {
+        self.expects_xevent_atom(w, self.xatom.NetWMPing)
+    }
+
+    /* _NET_WM_PING with serial as the timestamp, w echoes it back to the root */
+    pub fn ping_client(&self, w: Window, serial: i64) -> bool {
+        if !self.supports_ping(w) {
+            return false;
+        }
+        let mut msg: xlib::XClientMessageEvent = unsafe { mem::zeroed() };
+        msg.type_ = xlib::ClientMessage;
+        msg.window = w;
+        msg.message_type = self.xatom.WMProtocols;
+        msg.format = 32;
+        msg.data.set_long(0, self.xatom.NetWMPing as i64);
+        msg.data.set_long(1, serial);
+        msg.data.set_long(2, w as i64);
+        let mut ev: xlib::XEvent = msg.into();
+        unsafe { (self.lib.XSendEvent)(self.display, w, 0, xlib::NoEventMask, &mut ev) };
+        true
+    }
+
+    /* Asks w to close itself, false if it does not take WM_DELETE_WINDOW */
+    pub fn close_client(&self, w: Window) -> bool {
+        self.send_xevent_atom(w, self.xatom.WMDelete)
+    }
+
     pub fn kill_client(&self, w: Window) -> bool {
-        if !self.send_xevent_atom(w, self.xatom.WMDelete) {
-            unsafe {
-                (self.lib.XGrabServer)(self.display);
-                (self.lib.XSetCloseDownMode)(self.display, xlib::DestroyAll);
-                (self.lib.XKillClient)(self.display, w);
-                (self.lib.XSync)(self.display, xlib::False);
-                (self.lib.XUngrabServer)(self.display);
-            }
+        unsafe {
+            (self.lib.XGrabServer)(self.display);
+            (self.lib.XSetCloseDownMode)(self.display, xlib::DestroyAll);
+            (self.lib.XKillClient)(self.display, w);
+            (self.lib.XSync)(self.display, xlib::False);
+            (self.lib.XUngrabServer)(self.display);
         }
 
         !self.get_top_level_windows().contains(&w)
@@ -945,6 +1339,16 @@ impl XlibWrapper {
         }
     }
 
+    /* File descriptor of the X connection, readable when events come in */
+    pub fn connection_fd(&self) -> RawFd {
+        unsafe { (self.lib.XConnectionNumber)(self.display) }
+    }
+
+    /* Flushes the output buffer and counts the events read so far */
+    pub fn pending(&self) -> i32 {
+        unsafe { (self.lib.XPending)(self.display) }
+    }
+
     pub fn next_event(&self) -> xlib::XEvent {
         unsafe {
             let mut event: xlib::XEvent = mem::uninitialized();
@@ -962,6 +1366,16 @@ impl XlibWrapper {
         }
     }
 
+    /* Stacks windows from the top down, windows not in the list keep their place */
+    pub fn restack_windows(&self, mut windows: Vec<Window>) {
+        if windows.is_empty() {
+            return;
+        }
+        unsafe {
+            (self.lib.XRestackWindows)(self.display, windows.as_mut_ptr(), windows.len() as i32);
+        }
+    }
+
     pub fn resize_window(&self, w: Window, size: Size) {
         unsafe {
             (self.lib.XResizeWindow)(self.display, w, size.width as u32, size.height as u32);
@@ -1077,6 +1491,30 @@ impl XlibWrapper {
         }
     }
 
+    pub fn get_size_hints(&self, w: Window) -> SizeHints {
+        let mut hints = MaybeUninit::<xlib::XSizeHints>::zeroed();
+        let mut supplied: c_long = 0;
+        let status =
+            unsafe { (self.lib.XGetWMNormalHints)(self.display, w, hints.as_mut_ptr(), &mut supplied) };
+        if status == 0 {
+            return SizeHints::default();
+        }
+        let hints = unsafe { hints.assume_init() };
+        let size_if = |flag: c_long, width: i32, height: i32| {
+            if hints.flags & flag == flag {
+                Some(Size { width, height })
+            } else {
+                None
+            }
+        };
+        SizeHints {
+            min: size_if(xlib::PMinSize, hints.min_width, hints.min_height),
+            max: size_if(xlib::PMaxSize, hints.max_width, hints.max_height),
+            base: size_if(xlib::PBaseSize, hints.base_width, hints.base_height),
+            inc: size_if(xlib::PResizeInc, hints.width_inc, hints.height_inc),
+        }
+    }
+
     pub fn get_window_type(&self, window: xlib::Window) -> WindowType {
         if let Some(value) = self.get_atom_prop_value(window, self.xatom.NetWMWindowType) {
             if value == self.xatom.NetWMWindowTypeDesktop {
@@ -1100,6 +1538,9 @@ impl XlibWrapper {
             if value == self.xatom.NetWMWindowTypeDialog {
                 return WindowType::Dialog;
             }
+            if value == self.xatom.NetWMWindowTypeNotification {
+                return WindowType::Notification;
+            }
         }
         WindowType::Normal
     }
@@ -3,10 +3,26 @@ use x11_dl::xlib;
 pub struct Cursor {
     pub normal_cursor: u64,
     pub move_cursor: u64,
+    pub resize_nw_cursor: u64,
+    pub resize_ne_cursor: u64,
+    pub resize_sw_cursor: u64,
+    pub resize_se_cursor: u64,
+    pub resize_n_cursor: u64,
+    pub resize_e_cursor: u64,
+    pub resize_s_cursor: u64,
+    pub resize_w_cursor: u64,
 }
 
 const NORMAL: u32 = 68;
 const MOVE: u32 = 52;
+const TOP_LEFT_CORNER: u32 = 134;
+const TOP_RIGHT_CORNER: u32 = 136;
+const BOTTOM_LEFT_CORNER: u32 = 12;
+const BOTTOM_RIGHT_CORNER: u32 = 14;
+const TOP_SIDE: u32 = 138;
+const RIGHT_SIDE: u32 = 96;
+const BOTTOM_SIDE: u32 = 16;
+const LEFT_SIDE: u32 = 70;
 
 impl Cursor {
     pub fn new(xlib: &xlib::Xlib, disp: *mut xlib::Display) -> Self {
@@ -14,6 +30,14 @@ impl Cursor {
             Cursor {
                 normal_cursor: (xlib.XCreateFontCursor)(disp, NORMAL),
                 move_cursor: (xlib.XCreateFontCursor)(disp, MOVE),
+                resize_nw_cursor: (xlib.XCreateFontCursor)(disp, TOP_LEFT_CORNER),
+                resize_ne_cursor: (xlib.XCreateFontCursor)(disp, TOP_RIGHT_CORNER),
+                resize_sw_cursor: (xlib.XCreateFontCursor)(disp, BOTTOM_LEFT_CORNER),
+                resize_se_cursor: (xlib.XCreateFontCursor)(disp, BOTTOM_RIGHT_CORNER),
+                resize_n_cursor: (xlib.XCreateFontCursor)(disp, TOP_SIDE),
+                resize_e_cursor: (xlib.XCreateFontCursor)(disp, RIGHT_SIDE),
+                resize_s_cursor: (xlib.XCreateFontCursor)(disp, BOTTOM_SIDE),
+                resize_w_cursor: (xlib.XCreateFontCursor)(disp, LEFT_SIDE),
             }
         }
     }
@@ -0,0 +1,393 @@
+#![allow(dead_code)]
+use super::{util::*, window_system::WindowSystem, xatom::XAtom, xlibmodels::*};
+use crate::models::{
+    dockarea::DockArea, rect::Rect, screen::Screen, window_type::WindowType, Corner,
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
+use x11_dl::xlib;
+
+/* Calls that change a window, in the order the fake got them */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Call {
+    Map(Window),
+    Unmap(Window),
+    Move(Window, Position),
+    Resize(Window, Size),
+    Configure(Window),
+    Focus(Window),
+    Close(Window),
+    Kill(Window),
+    Ping(Window, i64),
+    Restack(Vec<Window>),
+    BorderColor(Window, u64),
+}
+
+/* What a client would have told the X server about itself */
+#[derive(Clone, Debug)]
+pub struct FakeWindow {
+    pub geometry: Geometry,
+    pub mapped: bool,
+    pub title: Option<String>,
+    pub window_type: WindowType,
+    pub transient_for: Option<Window>,
+    pub states: Vec<xlib::Atom>,
+    pub size_hints: SizeHints,
+    pub strut: Option<DockArea>,
+    pub takes_delete: bool,
+    pub takes_ping: bool,
+}
+
+impl Default for FakeWindow {
+    fn default() -> Self {
+        Self {
+            geometry: Geometry {
+                x: 0,
+                y: 0,
+                width: 100,
+                height: 100,
+            },
+            mapped: false,
+            title: None,
+            window_type: WindowType::Normal,
+            transient_for: None,
+            states: vec![],
+            size_hints: SizeHints::default(),
+            strut: None,
+            takes_delete: true,
+            takes_ping: false,
+        }
+    }
+}
+
+/*
+ * Window system kept in memory for tests. Windows are plain records that the
+ * calls update, and every call that changes one is logged to assert on
+ */
+pub struct FakeWindowSystem {
+    xatom: XAtom,
+    root: Window,
+    screens: Vec<Screen>,
+    windows: RefCell<HashMap<Window, FakeWindow>>,
+    next_window: Cell<Window>,
+    focus: Cell<Window>,
+    pointer: Cell<Position>,
+    calls: RefCell<Vec<Call>>,
+}
+
+impl FakeWindowSystem {
+    /* The root window is the one of the first screen */
+    pub fn new(screens: Vec<Screen>) -> Self {
+        let mut next_atom = 0;
+        let root = screens.first().map_or(1, |screen| screen.root);
+        Self {
+            xatom: XAtom::with(|_| {
+                next_atom += 1;
+                next_atom
+            }),
+            root,
+            screens,
+            windows: RefCell::new(HashMap::new()),
+            next_window: Cell::new(root + 1),
+            focus: Cell::new(root),
+            pointer: Cell::new(Position { x: 0, y: 0 }),
+            calls: RefCell::new(vec![]),
+        }
+    }
+
+    /* A single screen of width x height at the origin */
+    pub fn with_screen(width: i32, height: i32) -> Self {
+        Self::new(vec![Screen::new(1, width, height, 0, 0)])
+    }
+
+    pub fn add_window(&self, window: FakeWindow) -> Window {
+        let w = self.next_window.get();
+        self.next_window.set(w + 1);
+        self.windows.borrow_mut().insert(w, window);
+        w
+    }
+
+    /* Adds a window under an id that is already known, e.g. from a recording */
+    pub fn insert_window(&self, w: Window, window: FakeWindow) {
+        self.windows.borrow_mut().insert(w, window);
+    }
+
+    pub fn window(&self, w: Window) -> Option<FakeWindow> {
+        self.windows.borrow().get(&w).cloned()
+    }
+
+    pub fn focused(&self) -> Window {
+        self.focus.get()
+    }
+
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.borrow().clone()
+    }
+
+    pub fn clear_calls(&self) {
+        self.calls.borrow_mut().clear();
+    }
+
+    fn record(&self, call: Call) {
+        self.calls.borrow_mut().push(call);
+    }
+
+    fn with_window<F: FnOnce(&mut FakeWindow)>(&self, w: Window, f: F) {
+        if let Some(window) = self.windows.borrow_mut().get_mut(&w) {
+            f(window);
+        }
+    }
+}
+
+impl WindowSystem for FakeWindowSystem {
+    fn xatom(&self) -> &XAtom {
+        &self.xatom
+    }
+
+    fn add_to_root_net_client_list(&self, _w: Window) {}
+
+    fn add_to_save_set(&self, _w: Window) {}
+
+    fn center_cursor(&self, w: Window) {
+        let geom = self.get_geometry(w);
+        self.pointer.set(Position {
+            x: geom.x + geom.width as i32 / 2,
+            y: geom.y + geom.height as i32 / 2,
+        });
+    }
+
+    fn close_client(&self, w: Window) -> bool {
+        self.record(Call::Close(w));
+        self.window(w).is_some_and(|window| window.takes_delete)
+    }
+
+    fn configure_window(&self, window: Window, value_mask: Mask, changes: WindowChanges) {
+        let mask = value_mask as u32;
+        self.with_window(window, |window| {
+            if mask & xlib::CWX as u32 != 0 {
+                window.geometry.x = changes.x;
+            }
+            if mask & xlib::CWY as u32 != 0 {
+                window.geometry.y = changes.y;
+            }
+            if mask & xlib::CWWidth as u32 != 0 {
+                window.geometry.width = changes.width as u32;
+            }
+            if mask & xlib::CWHeight as u32 != 0 {
+                window.geometry.height = changes.height as u32;
+            }
+        });
+        self.record(Call::Configure(window));
+    }
+
+    fn exit(&self) {}
+
+    fn flush(&self) {}
+
+    fn get_geometry(&self, w: Window) -> Geometry {
+        self.window(w)
+            .map(|window| window.geometry)
+            .unwrap_or_default()
+    }
+
+    fn get_root(&self) -> Window {
+        self.root
+    }
+
+    fn get_screens(&self) -> Vec<Screen> {
+        self.screens.clone()
+    }
+
+    fn get_size_hints(&self, w: Window) -> SizeHints {
+        self.window(w)
+            .map(|window| window.size_hints)
+            .unwrap_or_default()
+    }
+
+    fn get_window_states_atoms(&self, window: xlib::Window) -> Vec<xlib::Atom> {
+        self.window(window)
+            .map(|window| window.states)
+            .unwrap_or_default()
+    }
+
+    fn get_window_strut_array(&self, window: Window) -> Option<DockArea> {
+        self.window(window)?.strut
+    }
+
+    fn get_window_title(&self, w: Window) -> Option<String> {
+        self.window(w)?.title
+    }
+
+    fn get_window_type(&self, window: xlib::Window) -> WindowType {
+        self.window(window)
+            .map(|window| window.window_type)
+            .unwrap_or(WindowType::Normal)
+    }
+
+    fn grab_button(
+        &self,
+        _button: u32,
+        _modifiers: u32,
+        _grab_window: Window,
+        _owner_events: bool,
+        _event_mask: u32,
+        _pointer_mode: i32,
+        _keyboard_mode: i32,
+        _confine_to: Window,
+        _cursor: u64,
+    ) {
+    }
+
+    fn grab_keyboard(&self) -> bool {
+        true
+    }
+
+    fn grab_keys(&self, _w: Window, _keysym: u32, _modifiers: u32) {}
+
+    fn grab_pointer(&self, _corner: Option<Corner>) -> bool {
+        true
+    }
+
+    fn is_viewable(&self, w: Window) -> bool {
+        self.window(w).is_some_and(|window| window.mapped)
+    }
+
+    fn keycode_to_key_sym(&self, _keycode: KeyCode) -> String {
+        String::new()
+    }
+
+    fn kill_client(&self, w: Window) -> bool {
+        self.record(Call::Kill(w));
+        self.windows.borrow_mut().remove(&w);
+        true
+    }
+
+    fn map_window(&self, window: Window) {
+        self.with_window(window, |window| window.mapped = true);
+        self.record(Call::Map(window));
+    }
+
+    fn move_cursor(&self, pos: Position) {
+        self.pointer.set(pos);
+    }
+
+    fn move_window(&self, w: Window, position: Position) {
+        self.with_window(w, |window| {
+            window.geometry.x = position.x;
+            window.geometry.y = position.y;
+        });
+        self.record(Call::Move(w, position));
+    }
+
+    fn ping_client(&self, w: Window, serial: i64) -> bool {
+        if !self.supports_ping(w) {
+            return false;
+        }
+        self.record(Call::Ping(w, serial));
+        true
+    }
+
+    fn pointer_pos(&self, _w: Window) -> Position {
+        self.pointer.get()
+    }
+
+    fn remove_focus(&self, w: Window) {
+        if self.focus.get() == w {
+            self.focus.set(self.root);
+        }
+    }
+
+    fn resize_window(&self, w: Window, size: Size) {
+        self.with_window(w, |window| {
+            window.geometry.width = size.width.max(1) as u32;
+            window.geometry.height = size.height.max(1) as u32;
+        });
+        self.record(Call::Resize(w, size));
+    }
+
+    fn restack_windows(&self, windows: Vec<Window>) {
+        self.record(Call::Restack(windows));
+    }
+
+    fn select_input(&self, _window: xlib::Window, _masks: Mask) {}
+
+    fn set_border_color(&self, w: Window, color: Color) {
+        self.record(Call::BorderColor(w, color.value()));
+    }
+
+    fn set_border_width(&self, _w: Window, _border_width: u32) {}
+
+    fn set_frame_extents(
+        &self,
+        _window: xlib::Window,
+        _left: i32,
+        _right: i32,
+        _top: i32,
+        _bottom: i32,
+    ) {
+    }
+
+    fn set_move_cursor(&self) {}
+
+    fn set_net_wm_desktop(&self, _window: xlib::Window, _desktop: u32) {}
+
+    fn set_resize_cursor(&self, _corner: Corner) {}
+
+    fn set_window_states_atoms(&self, window: xlib::Window, states: Vec<xlib::Atom>) {
+        self.with_window(window, |window| window.states = states);
+    }
+
+    fn set_wm_state(&self, _window: xlib::Window, _normal: bool) {}
+
+    fn should_be_managed(&self, w: Window) -> bool {
+        self.window(w).is_some_and(|window| {
+            window.window_type == WindowType::Normal && window.transient_for.is_none()
+        })
+    }
+
+    fn str_to_keycode(&self, _key: &str) -> Option<KeyCode> {
+        None
+    }
+
+    fn supports_ping(&self, w: Window) -> bool {
+        self.window(w).is_some_and(|window| window.takes_ping)
+    }
+
+    fn sync(&self, _discard: bool) {}
+
+    fn take_focus(&self, w: Window) {
+        self.focus.set(w);
+        self.record(Call::Focus(w));
+    }
+
+    fn top_level_window_count(&self) -> u32 {
+        self.windows.borrow().len() as u32
+    }
+
+    fn transient_for_hint(&self, w: Window) -> Option<Window> {
+        self.window(w)?.transient_for
+    }
+
+    fn ungrab_all_buttons(&self, _w: Window) {}
+
+    fn ungrab_keyboard(&self) {}
+
+    fn ungrab_pointer(&self) {}
+
+    fn unmap_window(&self, w: Window) {
+        self.with_window(w, |window| window.mapped = false);
+        self.record(Call::Unmap(w));
+    }
+
+    fn update_desktops(&self, _current_ws: u32, _num_of_ws: Option<u32>) {}
+
+    fn update_net_client_list(&self, _clients: Vec<Window>) {}
+
+    fn update_net_client_list_stacking(&self, _clients: &[Window]) {}
+
+    fn update_net_desktop_geometry(&self, _size: Size) {}
+
+    fn update_net_workarea(&self, _work_areas: &[Rect]) {}
+}
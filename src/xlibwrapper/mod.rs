@@ -1,7 +1,9 @@
 pub mod action;
 pub mod core;
 pub mod cursor;
+pub mod fake;
 pub mod masks;
 pub mod util;
+pub mod window_system;
 pub mod xatom;
 pub mod xlibmodels;
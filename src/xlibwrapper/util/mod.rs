@@ -1,14 +1,14 @@
 pub mod keysym_lookup;
 
-use serde::{self, de, Deserialize, Deserializer, Serialize};
+use serde::{self, de, Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Size {
     pub width: i32,
     pub height: i32,
@@ -27,16 +27,30 @@ pub enum Color {
     DefaultBackground,
     DefaultFocusedBackground,
     DefaultBorder,
-    #[serde(deserialize_with = "color_deserialize")]
+    #[serde(serialize_with = "color_serialize", deserialize_with = "color_deserialize")]
     Custom(u64),
 }
 
+/* Written the way it is read, so a serialized config can be loaded again */
+fn color_serialize<S>(value: &u64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format!("#{:06x}", value))
+}
+
 fn color_deserialize<'de, D>(desierializer: D) -> Result<u64, D::Error>
 where
     D: Deserializer<'de>,
 {
     let s: String = Deserialize::deserialize(desierializer)?;
     let without_prefix = s.trim_start_matches("#");
+    if without_prefix.len() != 6 {
+        return Err(de::Error::custom(format!(
+            "Failed to deserialize color: expected #rrggbb, got {:?}",
+            s
+        )));
+    }
     match u64::from_str_radix(without_prefix, 16) {
         Ok(res) => Ok(res),
         Err(e) => Err(de::Error::custom(format!(
@@ -0,0 +1,335 @@
+use super::{core::XlibWrapper, util::*, xatom::XAtom, xlibmodels::*};
+use crate::models::{
+    dockarea::DockArea, rect::Rect, screen::Screen, window_type::WindowType, Corner,
+};
+use x11_dl::xlib;
+
+/*
+ * Everything the reducers, wm and the reactor ask of the window system, X in
+ * practice. Keeping them behind this lets the logic run against a fake in tests
+ */
+pub trait WindowSystem {
+    fn xatom(&self) -> &XAtom;
+    fn add_to_root_net_client_list(&self, w: Window);
+    fn add_to_save_set(&self, w: Window);
+    fn center_cursor(&self, w: Window);
+    fn close_client(&self, w: Window) -> bool;
+    fn configure_window(&self, window: Window, value_mask: Mask, changes: WindowChanges);
+    fn exit(&self);
+    fn flush(&self);
+    fn get_geometry(&self, w: Window) -> Geometry;
+    fn get_root(&self) -> Window;
+    fn get_screens(&self) -> Vec<Screen>;
+    fn get_size_hints(&self, w: Window) -> SizeHints;
+    fn get_window_states_atoms(&self, window: xlib::Window) -> Vec<xlib::Atom>;
+    fn get_window_strut_array(&self, window: Window) -> Option<DockArea>;
+    fn get_window_title(&self, w: Window) -> Option<String>;
+    fn get_window_type(&self, window: xlib::Window) -> WindowType;
+    #[allow(clippy::too_many_arguments)]
+    fn grab_button(
+        &self,
+        button: u32,
+        modifiers: u32,
+        grab_window: Window,
+        owner_events: bool,
+        event_mask: u32,
+        pointer_mode: i32,
+        keyboard_mode: i32,
+        confine_to: Window,
+        cursor: u64,
+    );
+    fn grab_keyboard(&self) -> bool;
+    fn grab_keys(&self, w: Window, keysym: u32, modifiers: u32);
+    fn grab_pointer(&self, corner: Option<Corner>) -> bool;
+    fn is_viewable(&self, w: Window) -> bool;
+    fn keycode_to_key_sym(&self, keycode: KeyCode) -> String;
+    fn kill_client(&self, w: Window) -> bool;
+    fn map_window(&self, window: Window);
+    fn move_cursor(&self, pos: Position);
+    fn move_window(&self, w: Window, position: Position);
+    fn ping_client(&self, w: Window, serial: i64) -> bool;
+    fn pointer_pos(&self, w: Window) -> Position;
+    fn remove_focus(&self, w: Window);
+    fn resize_window(&self, w: Window, size: Size);
+    fn restack_windows(&self, windows: Vec<Window>);
+    fn select_input(&self, window: xlib::Window, masks: Mask);
+    fn set_border_color(&self, w: Window, color: Color);
+    fn set_border_width(&self, w: Window, border_width: u32);
+    fn set_frame_extents(&self, window: xlib::Window, left: i32, right: i32, top: i32, bottom: i32);
+    fn set_move_cursor(&self);
+    fn set_net_wm_desktop(&self, window: xlib::Window, desktop: u32);
+    fn set_resize_cursor(&self, corner: Corner);
+    fn set_window_states_atoms(&self, window: xlib::Window, states: Vec<xlib::Atom>);
+    fn set_wm_state(&self, window: xlib::Window, normal: bool);
+    fn should_be_managed(&self, w: Window) -> bool;
+    fn str_to_keycode(&self, key: &str) -> Option<KeyCode>;
+    fn supports_ping(&self, w: Window) -> bool;
+    fn sync(&self, discard: bool);
+    fn take_focus(&self, w: Window);
+    fn top_level_window_count(&self) -> u32;
+    fn transient_for_hint(&self, w: Window) -> Option<Window>;
+    fn ungrab_all_buttons(&self, w: Window);
+    fn ungrab_keyboard(&self);
+    fn ungrab_pointer(&self);
+    fn unmap_window(&self, w: Window);
+    fn update_desktops(&self, current_ws: u32, num_of_ws: Option<u32>);
+    fn update_net_client_list(&self, clients: Vec<Window>);
+    fn update_net_client_list_stacking(&self, clients: &[Window]);
+    fn update_net_desktop_geometry(&self, size: Size);
+    fn update_net_workarea(&self, work_areas: &[Rect]);
+}
+
+impl WindowSystem for XlibWrapper {
+    fn xatom(&self) -> &XAtom {
+        &self.xatom
+    }
+
+    fn add_to_root_net_client_list(&self, w: Window) {
+        XlibWrapper::add_to_root_net_client_list(self, w)
+    }
+
+    fn add_to_save_set(&self, w: Window) {
+        XlibWrapper::add_to_save_set(self, w)
+    }
+
+    fn center_cursor(&self, w: Window) {
+        XlibWrapper::center_cursor(self, w)
+    }
+
+    fn close_client(&self, w: Window) -> bool {
+        XlibWrapper::close_client(self, w)
+    }
+
+    fn configure_window(&self, window: Window, value_mask: Mask, changes: WindowChanges) {
+        XlibWrapper::configure_window(self, window, value_mask, changes)
+    }
+
+    fn exit(&self) {
+        XlibWrapper::exit(self)
+    }
+
+    fn flush(&self) {
+        XlibWrapper::flush(self)
+    }
+
+    fn get_geometry(&self, w: Window) -> Geometry {
+        XlibWrapper::get_geometry(self, w)
+    }
+
+    fn get_root(&self) -> Window {
+        XlibWrapper::get_root(self)
+    }
+
+    fn get_screens(&self) -> Vec<Screen> {
+        XlibWrapper::get_screens(self)
+    }
+
+    fn get_size_hints(&self, w: Window) -> SizeHints {
+        XlibWrapper::get_size_hints(self, w)
+    }
+
+    fn get_window_states_atoms(&self, window: xlib::Window) -> Vec<xlib::Atom> {
+        XlibWrapper::get_window_states_atoms(self, window)
+    }
+
+    fn get_window_strut_array(&self, window: Window) -> Option<DockArea> {
+        XlibWrapper::get_window_strut_array(self, window)
+    }
+
+    fn get_window_title(&self, w: Window) -> Option<String> {
+        XlibWrapper::get_window_title(self, w)
+    }
+
+    fn get_window_type(&self, window: xlib::Window) -> WindowType {
+        XlibWrapper::get_window_type(self, window)
+    }
+
+    fn grab_button(
+        &self,
+        button: u32,
+        modifiers: u32,
+        grab_window: Window,
+        owner_events: bool,
+        event_mask: u32,
+        pointer_mode: i32,
+        keyboard_mode: i32,
+        confine_to: Window,
+        cursor: u64,
+    ) {
+        XlibWrapper::grab_button(
+            self,
+            button,
+            modifiers,
+            grab_window,
+            owner_events,
+            event_mask,
+            pointer_mode,
+            keyboard_mode,
+            confine_to,
+            cursor,
+        )
+    }
+
+    fn grab_keyboard(&self) -> bool {
+        XlibWrapper::grab_keyboard(self)
+    }
+
+    fn grab_keys(&self, w: Window, keysym: u32, modifiers: u32) {
+        XlibWrapper::grab_keys(self, w, keysym, modifiers)
+    }
+
+    fn grab_pointer(&self, corner: Option<Corner>) -> bool {
+        XlibWrapper::grab_pointer(self, corner)
+    }
+
+    fn is_viewable(&self, w: Window) -> bool {
+        XlibWrapper::is_viewable(self, w)
+    }
+
+    fn keycode_to_key_sym(&self, keycode: KeyCode) -> String {
+        XlibWrapper::keycode_to_key_sym(self, keycode)
+    }
+
+    fn kill_client(&self, w: Window) -> bool {
+        XlibWrapper::kill_client(self, w)
+    }
+
+    fn map_window(&self, window: Window) {
+        XlibWrapper::map_window(self, window)
+    }
+
+    fn move_cursor(&self, pos: Position) {
+        XlibWrapper::move_cursor(self, pos)
+    }
+
+    fn move_window(&self, w: Window, position: Position) {
+        XlibWrapper::move_window(self, w, position)
+    }
+
+    fn ping_client(&self, w: Window, serial: i64) -> bool {
+        XlibWrapper::ping_client(self, w, serial)
+    }
+
+    fn pointer_pos(&self, w: Window) -> Position {
+        XlibWrapper::pointer_pos(self, w)
+    }
+
+    fn remove_focus(&self, w: Window) {
+        XlibWrapper::remove_focus(self, w)
+    }
+
+    fn resize_window(&self, w: Window, size: Size) {
+        XlibWrapper::resize_window(self, w, size)
+    }
+
+    fn restack_windows(&self, windows: Vec<Window>) {
+        XlibWrapper::restack_windows(self, windows)
+    }
+
+    fn select_input(&self, window: xlib::Window, masks: Mask) {
+        XlibWrapper::select_input(self, window, masks)
+    }
+
+    fn set_border_color(&self, w: Window, color: Color) {
+        XlibWrapper::set_border_color(self, w, color)
+    }
+
+    fn set_border_width(&self, w: Window, border_width: u32) {
+        XlibWrapper::set_border_width(self, w, border_width)
+    }
+
+    fn set_frame_extents(
+        &self,
+        window: xlib::Window,
+        left: i32,
+        right: i32,
+        top: i32,
+        bottom: i32,
+    ) {
+        XlibWrapper::set_frame_extents(self, window, left, right, top, bottom)
+    }
+
+    fn set_move_cursor(&self) {
+        XlibWrapper::set_move_cursor(self)
+    }
+
+    fn set_net_wm_desktop(&self, window: xlib::Window, desktop: u32) {
+        XlibWrapper::set_net_wm_desktop(self, window, desktop)
+    }
+
+    fn set_resize_cursor(&self, corner: Corner) {
+        XlibWrapper::set_resize_cursor(self, corner)
+    }
+
+    fn set_window_states_atoms(&self, window: xlib::Window, states: Vec<xlib::Atom>) {
+        XlibWrapper::set_window_states_atoms(self, window, states)
+    }
+
+    fn set_wm_state(&self, window: xlib::Window, normal: bool) {
+        XlibWrapper::set_wm_state(self, window, normal)
+    }
+
+    fn should_be_managed(&self, w: Window) -> bool {
+        XlibWrapper::should_be_managed(self, w)
+    }
+
+    fn str_to_keycode(&self, key: &str) -> Option<KeyCode> {
+        XlibWrapper::str_to_keycode(self, key)
+    }
+
+    fn supports_ping(&self, w: Window) -> bool {
+        XlibWrapper::supports_ping(self, w)
+    }
+
+    fn sync(&self, discard: bool) {
+        XlibWrapper::sync(self, discard)
+    }
+
+    fn take_focus(&self, w: Window) {
+        XlibWrapper::take_focus(self, w)
+    }
+
+    fn top_level_window_count(&self) -> u32 {
+        XlibWrapper::top_level_window_count(self)
+    }
+
+    fn transient_for_hint(&self, w: Window) -> Option<Window> {
+        XlibWrapper::transient_for_hint(self, w)
+    }
+
+    fn ungrab_all_buttons(&self, w: Window) {
+        XlibWrapper::ungrab_all_buttons(self, w)
+    }
+
+    fn ungrab_keyboard(&self) {
+        XlibWrapper::ungrab_keyboard(self)
+    }
+
+    fn ungrab_pointer(&self) {
+        XlibWrapper::ungrab_pointer(self)
+    }
+
+    fn unmap_window(&self, w: Window) {
+        XlibWrapper::unmap_window(self, w)
+    }
+
+    fn update_desktops(&self, current_ws: u32, num_of_ws: Option<u32>) {
+        XlibWrapper::update_desktops(self, current_ws, num_of_ws)
+    }
+
+    fn update_net_client_list(&self, clients: Vec<Window>) {
+        XlibWrapper::update_net_client_list(self, clients)
+    }
+
+    fn update_net_client_list_stacking(&self, clients: &[Window]) {
+        XlibWrapper::update_net_client_list_stacking(self, clients)
+    }
+
+    fn update_net_desktop_geometry(&self, size: Size) {
+        XlibWrapper::update_net_desktop_geometry(self, size)
+    }
+
+    fn update_net_workarea(&self, work_areas: &[Rect]) {
+        XlibWrapper::update_net_workarea(self, work_areas)
+    }
+}
@@ -9,8 +9,13 @@ pub struct XAtom {
     pub WMProtocols: xlib::Atom,
     pub WMDelete: xlib::Atom,
     pub WMState: xlib::Atom,
+    pub WMChangeState: xlib::Atom,
     pub WMTakeFocus: xlib::Atom,
     pub NetActiveWindow: xlib::Atom,
+    pub NetCloseWindow: xlib::Atom,
+    pub NetWMPing: xlib::Atom,
+    pub NetMoveResizeWindow: xlib::Atom,
+    pub NetWMMoveResize: xlib::Atom,
     pub NetSupported: xlib::Atom,
     pub NetWMName: xlib::Atom,
     pub NetWMState: xlib::Atom,
@@ -36,14 +41,19 @@ pub struct XAtom {
     pub NetWMWindowTypeUtility: xlib::Atom,
     pub NetWMWindowTypeSplash: xlib::Atom,
     pub NetWMWindowTypeDialog: xlib::Atom,
+    pub NetWMWindowTypeNotification: xlib::Atom,
 
     pub NetSupportingWmCheck: xlib::Atom,
     pub NetClientList: xlib::Atom,
+    pub NetClientListStacking: xlib::Atom,
+    pub NetWorkarea: xlib::Atom,
+    pub NetDesktopGeometry: xlib::Atom,
     pub NetDesktopViewport: xlib::Atom,
     pub NetNumberOfDesktops: xlib::Atom,
     pub NetCurrentDesktop: xlib::Atom,
     pub NetDesktopNames: xlib::Atom,
     pub NetWMDesktop: xlib::Atom,
+    pub NetFrameExtents: xlib::Atom,
     pub NetWMStrutPartial: xlib::Atom, //net version - Reserve Screen Space
     pub NetWMStrut: xlib::Atom,        //old version
 
@@ -56,20 +66,24 @@ impl XAtom {
     pub fn net_supported(&self) -> Vec<xlib::Atom> {
         vec![
             self.NetActiveWindow,
+            self.NetCloseWindow,
+            self.NetWMPing,
+            self.NetMoveResizeWindow,
+            self.NetWMMoveResize,
             self.NetSupported,
             //self.NetWMName,
             self.NetWMState,
             //self.NetWMStateModal,
-            //self.NetWMStateSticky,
-            //self.NetWMStateMaximizedVert,
-            //self.NetWMStateMaximizedHorz,
+            self.NetWMStateSticky,
+            self.NetWMStateMaximizedVert,
+            self.NetWMStateMaximizedHorz,
             //self.NetWMStateShaded,
             //self.NetWMStateSkipTaskbar,
             //self.NetWMStateSkipPager,
-            //self.NetWMStateHidden,
+            self.NetWMStateHidden,
             self.NetWMStateFullscreen,
-            //self.NetWMStateAbove,
-            //self.NetWMStateBelow,
+            self.NetWMStateAbove,
+            self.NetWMStateBelow,
             //self.NetWMStateDemandsAttention,
             //self.NetWMWindowType,
             //self.NetWMWindowTypeDesktop,
@@ -79,13 +93,18 @@ impl XAtom {
             self.NetWMWindowTypeUtility,
             self.NetWMWindowTypeSplash,
             self.NetWMWindowTypeDialog,
+            self.NetWMWindowTypeNotification,
             self.NetSupportingWmCheck,
             self.NetClientList,
+            self.NetClientListStacking,
+            self.NetWorkarea,
+            self.NetDesktopGeometry,
             self.NetDesktopViewport,
             self.NetNumberOfDesktops,
             self.NetCurrentDesktop,
             self.NetDesktopNames,
             self.NetWMDesktop,
+            self.NetFrameExtents,
             self.NetWMStrutPartial,
             self.NetWMStrut,
         ]
@@ -101,12 +120,27 @@ impl XAtom {
         if atom == self.WMState {
             return "WM_STATE";
         }
+        if atom == self.WMChangeState {
+            return "WM_CHANGE_STATE";
+        }
         if atom == self.WMTakeFocus {
             return "WM_TAKE_FOCUS";
         }
         if atom == self.NetActiveWindow {
             return "_NET_ACTIVE_WINDOW";
         }
+        if atom == self.NetWMPing {
+            return "_NET_WM_PING";
+        }
+        if atom == self.NetCloseWindow {
+            return "_NET_CLOSE_WINDOW";
+        }
+        if atom == self.NetMoveResizeWindow {
+            return "_NET_MOVERESIZE_WINDOW";
+        }
+        if atom == self.NetWMMoveResize {
+            return "_NET_WM_MOVERESIZE";
+        }
         if atom == self.NetSupported {
             return "_NET_SUPPORTED";
         }
@@ -159,12 +193,24 @@ impl XAtom {
         if atom == self.NetWMWindowTypeDialog {
             return "_NET_WM_WINDOW_TYPE_DIALOG";
         }
+        if atom == self.NetWMWindowTypeNotification {
+            return "_NET_WM_WINDOW_TYPE_NOTIFICATION";
+        }
         if atom == self.NetWMWindowTypeDock {
             return "_NET_WM_WINDOW_TYPE_DOCK";
         }
         if atom == self.NetClientList {
             return "_NET_CLIENT_LIST";
         }
+        if atom == self.NetClientListStacking {
+            return "_NET_CLIENT_LIST_STACKING";
+        }
+        if atom == self.NetWorkarea {
+            return "_NET_WORKAREA";
+        }
+        if atom == self.NetDesktopGeometry {
+            return "_NET_DESKTOP_GEOMETRY";
+        }
         if atom == self.NetDesktopViewport {
             return "_NET_DESKTOP_VIEWPORT";
         }
@@ -180,6 +226,9 @@ impl XAtom {
         if atom == self.NetWMDesktop {
             return "_NET_WM_DESKTOP";
         }
+        if atom == self.NetFrameExtents {
+            return "_NET_FRAME_EXTENTS";
+        }
         if atom == self.NetWMStrutPartial {
             return "_NET_WM_STRUT_PARTIAL";
         }
@@ -196,51 +245,66 @@ impl XAtom {
     }
 
     pub fn new(xlib: &xlib::Xlib, dpy: *mut xlib::Display) -> XAtom {
+        Self::with(|name| from(xlib, dpy, name))
+    }
+
+    /* Atoms by name from intern, any backend can hand out its own */
+    pub fn with<F: FnMut(&str) -> xlib::Atom>(mut intern: F) -> XAtom {
         XAtom {
-            WMProtocols: from(xlib, dpy, "WM_PROTOCOLS"),
-            WMDelete: from(xlib, dpy, "WM_DELETE_WINDOW"),
-            WMState: from(xlib, dpy, "WM_STATE"),
-            WMTakeFocus: from(xlib, dpy, "WM_TAKE_FOCUS"),
-            NetActiveWindow: from(xlib, dpy, "_NET_ACTIVE_WINDOW"),
-            NetSupported: from(xlib, dpy, "_NET_SUPPORTED"),
-            NetWMName: from(xlib, dpy, "_NET_WM_NAME"),
-
-            NetWMState: from(xlib, dpy, "_NET_WM_STATE"),
-            NetWMStateModal: from(xlib, dpy, "_NET_WM_STATE_MODAL"),
-            NetWMStateSticky: from(xlib, dpy, "_NET_WM_STATE_STICKY"),
-            NetWMStateMaximizedVert: from(xlib, dpy, "_NET_WM_STATE_MAXIMIZED_VERT"),
-            NetWMStateMaximizedHorz: from(xlib, dpy, "_NET_WM_STATE_MAXIMIZED_HORZ"),
-            NetWMStateShaded: from(xlib, dpy, "_NET_WM_STATE_SHADED"),
-            NetWMStateSkipTaskbar: from(xlib, dpy, "_NET_WM_STATE_SKIP_TASKBAR"),
-            NetWMStateSkipPager: from(xlib, dpy, "_NET_WM_STATE_SKIP_PAGER"),
-            NetWMStateHidden: from(xlib, dpy, "_NET_WM_STATE_HIDDEN"),
-            NetWMStateFullscreen: from(xlib, dpy, "_NET_WM_STATE_FULLSCREEN"),
-            NetWMStateAbove: from(xlib, dpy, "_NET_WM_STATE_ABOVE"),
-            NetWMStateBelow: from(xlib, dpy, "_NET_WM_STATE_BELOW"),
-            NetWMStateDemandsAttention: from(xlib, dpy, "_NET_WM_STATE_DEMANDS_ATTENTION"),
-
-            NetWMWindowType: from(xlib, dpy, "_NET_WM_WINDOW_TYPE"),
-            NetWMWindowTypeDesktop: from(xlib, dpy, "_NET_WM_WINDOW_TYPE_DESKTOP"),
-            NetWMWindowTypeDock: from(xlib, dpy, "_NET_WM_WINDOW_TYPE_DOCK"),
-            NetWMWindowTypeToolbar: from(xlib, dpy, "_NET_WM_WINDOW_TYPE_TOOLBAR"),
-            NetWMWindowTypeMenu: from(xlib, dpy, "_NET_WM_WINDOW_TYPE_MENU"),
-            NetWMWindowTypeUtility: from(xlib, dpy, "_NET_WM_WINDOW_TYPE_UTILITY"),
-            NetWMWindowTypeSplash: from(xlib, dpy, "_NET_WM_WINDOW_TYPE_SPLASH"),
-            NetWMWindowTypeDialog: from(xlib, dpy, "_NET_WM_WINDOW_TYPE_DIALOG"),
-            NetSupportingWmCheck: from(xlib, dpy, "_NET_SUPPORTING_WM_CHECK"),
-
-            NetClientList: from(xlib, dpy, "_NET_CLIENT_LIST"),
-            NetDesktopViewport: from(xlib, dpy, "_NET_DESKTOP_VIEWPORT"),
-            NetNumberOfDesktops: from(xlib, dpy, "_NET_NUMBER_OF_DESKTOPS"),
-            NetCurrentDesktop: from(xlib, dpy, "_NET_CURRENT_DESKTOP"),
-            NetDesktopNames: from(xlib, dpy, "_NET_DESKTOP_NAMES"),
-            NetWMDesktop: from(xlib, dpy, "_NET_WM_DESKTOP"),
-            NetWMStrutPartial: from(xlib, dpy, "_NET_WM_STRUT_PARTIAL"),
-            NetWMStrut: from(xlib, dpy, "_NET_WM_STRUT"),
-
-            WMNormalHints: from(xlib, dpy, "WM_NORMAL_HINTS"),
-            WMTransientFor: from(xlib, dpy, "WM_TRANSIENT_FOR"),
-            NetUtf8String: from(xlib, dpy, "UTF8_STRING"),
+            WMProtocols: intern("WM_PROTOCOLS"),
+            WMDelete: intern("WM_DELETE_WINDOW"),
+            WMState: intern("WM_STATE"),
+            WMChangeState: intern("WM_CHANGE_STATE"),
+            WMTakeFocus: intern("WM_TAKE_FOCUS"),
+            NetActiveWindow: intern("_NET_ACTIVE_WINDOW"),
+            NetCloseWindow: intern("_NET_CLOSE_WINDOW"),
+            NetWMPing: intern("_NET_WM_PING"),
+            NetMoveResizeWindow: intern("_NET_MOVERESIZE_WINDOW"),
+            NetWMMoveResize: intern("_NET_WM_MOVERESIZE"),
+            NetSupported: intern("_NET_SUPPORTED"),
+            NetWMName: intern("_NET_WM_NAME"),
+
+            NetWMState: intern("_NET_WM_STATE"),
+            NetWMStateModal: intern("_NET_WM_STATE_MODAL"),
+            NetWMStateSticky: intern("_NET_WM_STATE_STICKY"),
+            NetWMStateMaximizedVert: intern("_NET_WM_STATE_MAXIMIZED_VERT"),
+            NetWMStateMaximizedHorz: intern("_NET_WM_STATE_MAXIMIZED_HORZ"),
+            NetWMStateShaded: intern("_NET_WM_STATE_SHADED"),
+            NetWMStateSkipTaskbar: intern("_NET_WM_STATE_SKIP_TASKBAR"),
+            NetWMStateSkipPager: intern("_NET_WM_STATE_SKIP_PAGER"),
+            NetWMStateHidden: intern("_NET_WM_STATE_HIDDEN"),
+            NetWMStateFullscreen: intern("_NET_WM_STATE_FULLSCREEN"),
+            NetWMStateAbove: intern("_NET_WM_STATE_ABOVE"),
+            NetWMStateBelow: intern("_NET_WM_STATE_BELOW"),
+            NetWMStateDemandsAttention: intern("_NET_WM_STATE_DEMANDS_ATTENTION"),
+
+            NetWMWindowType: intern("_NET_WM_WINDOW_TYPE"),
+            NetWMWindowTypeDesktop: intern("_NET_WM_WINDOW_TYPE_DESKTOP"),
+            NetWMWindowTypeDock: intern("_NET_WM_WINDOW_TYPE_DOCK"),
+            NetWMWindowTypeToolbar: intern("_NET_WM_WINDOW_TYPE_TOOLBAR"),
+            NetWMWindowTypeMenu: intern("_NET_WM_WINDOW_TYPE_MENU"),
+            NetWMWindowTypeUtility: intern("_NET_WM_WINDOW_TYPE_UTILITY"),
+            NetWMWindowTypeSplash: intern("_NET_WM_WINDOW_TYPE_SPLASH"),
+            NetWMWindowTypeDialog: intern("_NET_WM_WINDOW_TYPE_DIALOG"),
+            NetWMWindowTypeNotification: intern("_NET_WM_WINDOW_TYPE_NOTIFICATION"),
+            NetSupportingWmCheck: intern("_NET_SUPPORTING_WM_CHECK"),
+
+            NetClientList: intern("_NET_CLIENT_LIST"),
+            NetClientListStacking: intern("_NET_CLIENT_LIST_STACKING"),
+            NetWorkarea: intern("_NET_WORKAREA"),
+            NetDesktopGeometry: intern("_NET_DESKTOP_GEOMETRY"),
+            NetDesktopViewport: intern("_NET_DESKTOP_VIEWPORT"),
+            NetNumberOfDesktops: intern("_NET_NUMBER_OF_DESKTOPS"),
+            NetCurrentDesktop: intern("_NET_CURRENT_DESKTOP"),
+            NetDesktopNames: intern("_NET_DESKTOP_NAMES"),
+            NetWMDesktop: intern("_NET_WM_DESKTOP"),
+            NetFrameExtents: intern("_NET_FRAME_EXTENTS"),
+            NetWMStrutPartial: intern("_NET_WM_STRUT_PARTIAL"),
+            NetWMStrut: intern("_NET_WM_STRUT"),
+
+            WMNormalHints: intern("WM_NORMAL_HINTS"),
+            WMTransientFor: intern("WM_TRANSIENT_FOR"),
+            NetUtf8String: intern("UTF8_STRING"),
         }
     }
 }
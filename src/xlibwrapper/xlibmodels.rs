@@ -1,5 +1,6 @@
 #![allow(unused_variables, dead_code, deprecated)]
 use super::util::*;
+use serde::{Deserialize, Serialize};
 use x11_dl::xlib;
 
 pub(crate) type Mask = i64;
@@ -10,7 +11,10 @@ pub(crate) type Time = xlib::Time;
 pub(crate) type KeyCode = xlib::KeyCode;
 pub(crate) type MonitorId = u32;
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+/* _NET_WM_DESKTOP of windows shown on every desktop */
+pub(crate) const ALL_DESKTOPS: u32 = 0xFFFF_FFFF;
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WindowChanges {
     pub x: i32,
     pub y: i32,
@@ -21,6 +25,7 @@ pub struct WindowChanges {
     pub stack_mode: i32,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Geometry {
     pub x: i32,
     pub y: i32,
@@ -28,6 +33,53 @@ pub struct Geometry {
     pub height: u32,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SizeHints {
+    pub min: Option<Size>,
+    pub max: Option<Size>,
+    pub base: Option<Size>,
+    pub inc: Option<Size>,
+}
+
+impl SizeHints {
+    /*
+     * Clamps size between min and max and snaps it to the resize increments,
+     * as described in ICCCM 4.1.2.3
+     */
+    pub fn constrain(&self, size: Size) -> Size {
+        let mut width = size.width.max(1);
+        let mut height = size.height.max(1);
+
+        if let Some(inc) = self.inc {
+            let base = self.base.or(self.min).unwrap_or(Size {
+                width: 0,
+                height: 0,
+            });
+            if inc.width > 0 && width > base.width {
+                width = base.width + ((width - base.width) / inc.width) * inc.width;
+            }
+            if inc.height > 0 && height > base.height {
+                height = base.height + ((height - base.height) / inc.height) * inc.height;
+            }
+        }
+
+        if let Some(min) = self.min {
+            width = width.max(min.width);
+            height = height.max(min.height);
+        }
+
+        if let Some(max) = self.max {
+            if max.width > 0 {
+                width = width.min(max.width);
+            }
+            if max.height > 0 {
+                height = height.min(max.height);
+            }
+        }
+        Size { width, height }
+    }
+}
+
 pub struct WindowAttributes<'a> {
     pub x: i32,
     pub y: i32,
@@ -117,3 +169,60 @@ impl<'a> Into<xlib::XWindowAttributes> for WindowAttributes<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn constrain_size_hints() {
+        let hints = SizeHints {
+            min: Some(Size {
+                width: 100,
+                height: 50,
+            }),
+            max: Some(Size {
+                width: 400,
+                height: 300,
+            }),
+            base: Some(Size {
+                width: 10,
+                height: 10,
+            }),
+            inc: Some(Size {
+                width: 7,
+                height: 13,
+            }),
+        };
+        assert_eq!(
+            hints.constrain(Size {
+                width: 20,
+                height: 20
+            }),
+            Size {
+                width: 100,
+                height: 50
+            }
+        );
+        assert_eq!(
+            hints.constrain(Size {
+                width: 200,
+                height: 200
+            }),
+            Size {
+                width: 199,
+                height: 192
+            }
+        );
+        assert_eq!(
+            hints.constrain(Size {
+                width: 1000,
+                height: 1000
+            }),
+            Size {
+                width: 400,
+                height: 300
+            }
+        );
+    }
+}
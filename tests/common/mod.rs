@@ -0,0 +1,405 @@
+#![allow(dead_code)]
+use std::{
+    env,
+    ffi::CString,
+    fs,
+    os::raw::*,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+use x11_dl::xlib;
+
+pub use xlib::Window;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/* Set to 1 to skip the tests where Xvfb isn't installed instead of failing them */
+const SKIP_ENV: &str = "HADLOCK_SKIP_XVFB";
+
+/* Tests run as threads of one process, each of them takes the next display */
+static NEXT_DISPLAY: AtomicU32 = AtomicU32::new(50);
+
+/* Polls f every 20ms until it returns Some, None once 5s have passed */
+pub fn wait_for<T, F: FnMut() -> Option<T>>(mut f: F) -> Option<T> {
+    let start = Instant::now();
+    loop {
+        if let Some(t) = f() {
+            return Some(t);
+        }
+        if start.elapsed() > TIMEOUT {
+            return None;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/* Xvfb on a free display, killed on drop */
+pub struct Xvfb {
+    pub display: String,
+    number: u32,
+    child: Child,
+}
+
+impl Xvfb {
+    /*
+     * Starts Xvfb with one screen per (width, height), joined left to right
+     * with xinerama. A missing Xvfb fails the test, unless HADLOCK_SKIP_XVFB=1
+     * asks for it to be skipped, then this is None
+     */
+    pub fn start(screens: &[(u32, u32)]) -> Option<Self> {
+        let number = loop {
+            let n = NEXT_DISPLAY.fetch_add(1, Ordering::SeqCst);
+            if !Path::new(&format!("/tmp/.X{}-lock", n)).exists()
+                && !Path::new(&format!("/tmp/.X11-unix/X{}", n)).exists()
+            {
+                break n;
+            }
+        };
+        let display = format!(":{}", number);
+
+        let mut cmd = Command::new("Xvfb");
+        cmd.arg(&display).args(["-nolisten", "tcp", "+xinerama"]);
+        for (i, (width, height)) in screens.iter().enumerate() {
+            cmd.arg("-screen")
+                .arg(i.to_string())
+                .arg(format!("{}x{}x24", width, height));
+        }
+        let child = match cmd.stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+            Ok(child) => child,
+            Err(e) if env::var(SKIP_ENV).is_ok_and(|skip| skip == "1") => {
+                eprintln!("Xvfb not available, skipping: {}", e);
+                return None;
+            }
+            Err(e) => panic!("Xvfb not available, set {}=1 to skip: {}", SKIP_ENV, e),
+        };
+        let xvfb = Self {
+            display,
+            number,
+            child,
+        };
+
+        let socket = format!("/tmp/.X11-unix/X{}", number);
+        wait_for(|| Some(()).filter(|_| Path::new(&socket).exists()))
+            .expect("Xvfb did not come up");
+        Some(xvfb)
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/* The hadlock binary running against an Xvfb with a config of its own */
+pub struct Hadlock {
+    child: Child,
+    config: PathBuf,
+}
+
+impl Hadlock {
+    /* Starts hadlock and waits until it has set _NET_SUPPORTING_WM_CHECK */
+    pub fn start(xvfb: &Xvfb, config: &str) -> Self {
+        let hadlock = Self::spawn(xvfb, config, &[]);
+        let client = Client::connect(xvfb);
+        let check = client.atom("_NET_SUPPORTING_WM_CHECK");
+        wait_for(|| client.root_property(check).first().copied())
+            .expect("hadlock did not start");
+        hadlock
+    }
+
+    /* Starts hadlock with extra arguments, without waiting for it */
+    pub fn spawn(xvfb: &Xvfb, config: &str, args: &[&str]) -> Self {
+        let path = env::temp_dir().join(format!(
+            "hadlock-test-{}-{}.json",
+            std::process::id(),
+            xvfb.number
+        ));
+        fs::write(&path, config).expect("Failed to write test config");
+
+        let child = Command::new(env!("CARGO_BIN_EXE_hadlock"))
+            .arg("--config")
+            .arg(&path)
+            .args(args)
+            .env("DISPLAY", &xvfb.display)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start hadlock");
+        Self {
+            child,
+            config: path,
+        }
+    }
+
+    /* Whether hadlock exited successfully, None if it is still running after 5s */
+    pub fn wait_exit(&mut self) -> Option<bool> {
+        wait_for(|| self.child.try_wait().ok().flatten()).map(|status| status.success())
+    }
+}
+
+impl Drop for Hadlock {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_file(&self.config);
+    }
+}
+
+/* An X client of its own, talks to the server through x11-dl like hadlock does */
+pub struct Client {
+    xlib: xlib::Xlib,
+    display: *mut xlib::Display,
+    pub root: Window,
+}
+
+impl Client {
+    pub fn connect(xvfb: &Xvfb) -> Self {
+        let xlib = xlib::Xlib::open().expect("Failed to load xlib");
+        let name = CString::new(xvfb.display.as_str()).unwrap();
+        let display = wait_for(|| {
+            let display = unsafe { (xlib.XOpenDisplay)(name.as_ptr()) };
+            Some(display).filter(|display| !display.is_null())
+        })
+        .expect("Failed to open display");
+        let root = unsafe { (xlib.XDefaultRootWindow)(display) };
+        Self {
+            xlib,
+            display,
+            root,
+        }
+    }
+
+    pub fn atom(&self, name: &str) -> xlib::Atom {
+        let name = CString::new(name).unwrap();
+        unsafe { (self.xlib.XInternAtom)(self.display, name.as_ptr(), xlib::False) }
+    }
+
+    pub fn sync(&self) {
+        unsafe {
+            (self.xlib.XSync)(self.display, xlib::False);
+        }
+    }
+
+    /* Creates a plain top level window and asks for it to be mapped */
+    pub fn map_new_window(&self, x: i32, y: i32, width: u32, height: u32) -> Window {
+        let w = unsafe {
+            (self.xlib.XCreateSimpleWindow)(self.display, self.root, x, y, width, height, 0, 0, 0)
+        };
+        self.map(w);
+        w
+    }
+
+    pub fn map(&self, w: Window) {
+        unsafe {
+            (self.xlib.XMapWindow)(self.display, w);
+        }
+        self.sync();
+    }
+
+    pub fn unmap(&self, w: Window) {
+        unsafe {
+            (self.xlib.XUnmapWindow)(self.display, w);
+        }
+        self.sync();
+    }
+
+    pub fn destroy(&self, w: Window) {
+        unsafe {
+            (self.xlib.XDestroyWindow)(self.display, w);
+        }
+        self.sync();
+    }
+
+    pub fn viewable(&self, w: Window) -> bool {
+        unsafe {
+            let mut attrs: xlib::XWindowAttributes = std::mem::zeroed();
+            (self.xlib.XGetWindowAttributes)(self.display, w, &mut attrs);
+            attrs.map_state == xlib::IsViewable
+        }
+    }
+
+    /* x, y, width and height of w in root coordinates */
+    pub fn geometry(&self, w: Window) -> (i32, i32, u32, u32) {
+        unsafe {
+            let (mut root, mut x, mut y, mut width, mut height, mut border, mut depth) =
+                (0, 0, 0, 0, 0, 0, 0);
+            (self.xlib.XGetGeometry)(
+                self.display,
+                w,
+                &mut root,
+                &mut x,
+                &mut y,
+                &mut width,
+                &mut height,
+                &mut border,
+                &mut depth,
+            );
+            let (mut root_x, mut root_y, mut child) = (0, 0, 0);
+            (self.xlib.XTranslateCoordinates)(
+                self.display,
+                w,
+                self.root,
+                0,
+                0,
+                &mut root_x,
+                &mut root_y,
+                &mut child,
+            );
+            (root_x, root_y, width, height)
+        }
+    }
+
+    /* A format 32 property of w, empty if it isn't set */
+    pub fn property(&self, w: Window, property: xlib::Atom) -> Vec<u64> {
+        unsafe {
+            let (mut actual_type, mut actual_format, mut nitems, mut bytes_after) = (0, 0, 0, 0);
+            let mut data: *mut c_uchar = ptr::null_mut();
+            let status = (self.xlib.XGetWindowProperty)(
+                self.display,
+                w,
+                property,
+                0,
+                1024,
+                xlib::False,
+                xlib::AnyPropertyType as u64,
+                &mut actual_type,
+                &mut actual_format,
+                &mut nitems,
+                &mut bytes_after,
+                &mut data,
+            );
+            if status != xlib::Success as i32 || data.is_null() {
+                return vec![];
+            }
+            let values = if actual_format == 32 {
+                std::slice::from_raw_parts(data as *const c_ulong, nitems as usize).to_vec()
+            } else {
+                vec![]
+            };
+            (self.xlib.XFree)(data as *mut c_void);
+            values
+        }
+    }
+
+    /* Sets a CARDINAL property on w, the way a previous window manager would have */
+    pub fn set_cardinal(&self, w: Window, property: &str, values: &[u64]) {
+        let data: Vec<c_ulong> = values.iter().map(|value| *value as c_ulong).collect();
+        unsafe {
+            (self.xlib.XChangeProperty)(
+                self.display,
+                w,
+                self.atom(property),
+                xlib::XA_CARDINAL,
+                32,
+                xlib::PropModeReplace,
+                data.as_ptr() as *const c_uchar,
+                data.len() as c_int,
+            );
+        }
+        self.sync();
+    }
+
+    pub fn root_property(&self, property: xlib::Atom) -> Vec<u64> {
+        self.property(self.root, property)
+    }
+
+    pub fn client_list(&self) -> Vec<Window> {
+        self.root_property(self.atom("_NET_CLIENT_LIST"))
+    }
+
+    pub fn current_desktop(&self) -> Option<u64> {
+        self.root_property(self.atom("_NET_CURRENT_DESKTOP"))
+            .first()
+            .copied()
+    }
+
+    pub fn focus(&self) -> Window {
+        unsafe {
+            let (mut focus, mut revert_to) = (0, 0);
+            (self.xlib.XGetInputFocus)(self.display, &mut focus, &mut revert_to);
+            focus
+        }
+    }
+
+    /* An EWMH client message about w, sent to root the way pagers and taskbars do */
+    pub fn send_message(&self, w: Window, message_type: &str, data: &[i64]) {
+        let mut event = xlib::XClientMessageEvent {
+            type_: xlib::ClientMessage,
+            serial: 0,
+            send_event: xlib::True,
+            display: self.display,
+            window: w,
+            message_type: self.atom(message_type),
+            format: 32,
+            data: xlib::ClientMessageData::new(),
+        };
+        for (i, value) in data.iter().enumerate() {
+            event.data.set_long(i, *value as c_long);
+        }
+        let mut event = xlib::XEvent::from(event);
+        unsafe {
+            (self.xlib.XSendEvent)(
+                self.display,
+                self.root,
+                xlib::False,
+                xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask,
+                &mut event,
+            );
+        }
+        self.sync();
+    }
+
+    /* A key press on root, as if keysym was typed with the modifiers in state */
+    pub fn press_key(&self, keysym: c_uint, state: c_uint) {
+        let keycode = unsafe { (self.xlib.XKeysymToKeycode)(self.display, keysym as xlib::KeySym) };
+        let event = xlib::XKeyEvent {
+            type_: xlib::KeyPress,
+            serial: 0,
+            send_event: xlib::True,
+            display: self.display,
+            window: self.root,
+            root: self.root,
+            subwindow: 0,
+            time: xlib::CurrentTime,
+            x: 0,
+            y: 0,
+            x_root: 0,
+            y_root: 0,
+            state,
+            keycode: keycode as c_uint,
+            same_screen: xlib::True,
+        };
+        let mut event = xlib::XEvent::from(event);
+        unsafe {
+            (self.xlib.XSendEvent)(
+                self.display,
+                self.root,
+                xlib::False,
+                xlib::KeyPressMask,
+                &mut event,
+            );
+        }
+        self.sync();
+    }
+
+    pub fn warp_pointer(&self, x: i32, y: i32) {
+        unsafe {
+            (self.xlib.XWarpPointer)(self.display, 0, self.root, 0, 0, 0, 0, x, y);
+        }
+        self.sync();
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        unsafe {
+            (self.xlib.XCloseDisplay)(self.display);
+        }
+    }
+}
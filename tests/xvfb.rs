@@ -0,0 +1,225 @@
+/*
+ * Runs hadlock against Xvfb and drives it with real X clients. Every test
+ * fails when Xvfb isn't installed, HADLOCK_SKIP_XVFB=1 skips them instead
+ */
+mod common;
+
+use common::*;
+use x11_dl::{keysym, xlib};
+
+const CONFIG: &str = r#"{
+    "borderWidth": 0,
+    "outerGap": 0,
+    "innerGap": 0,
+    "pingInterval": 0,
+    "closeTimeout": 0
+}"#;
+
+macro_rules! xvfb {
+    ($screens:expr) => {
+        match Xvfb::start($screens) {
+            Some(xvfb) => xvfb,
+            None => return,
+        }
+    };
+}
+
+fn overlap(a: (i32, i32, u32, u32), b: (i32, i32, u32, u32)) -> bool {
+    a.0 < b.0 + b.2 as i32 && b.0 < a.0 + a.2 as i32
+}
+
+fn focus(client: &Client, w: Window) {
+    client.send_message(w, "_NET_ACTIVE_WINDOW", &[2, 0, 0]);
+    wait_for(|| Some(()).filter(|_| client.focus() == w)).expect("window never got focus");
+}
+
+#[test]
+fn map_and_destroy_updates_client_list() {
+    let xvfb = xvfb!(&[(800, 600)]);
+    let _hadlock = Hadlock::start(&xvfb, CONFIG);
+    let client = Client::connect(&xvfb);
+
+    let w = client.map_new_window(0, 0, 100, 100);
+    wait_for(|| Some(()).filter(|_| client.viewable(w) && client.client_list().contains(&w)))
+        .expect("window was not managed");
+
+    // Floating places new windows in the middle of the screen
+    assert_eq!(client.geometry(w), (80, 120, 640, 360));
+    focus(&client, w);
+
+    client.destroy(w);
+    wait_for(|| Some(()).filter(|_| !client.client_list().contains(&w)))
+        .expect("destroyed window still in _NET_CLIENT_LIST");
+}
+
+#[test]
+fn unmapped_window_leaves_client_list_and_tiling() {
+    let xvfb = xvfb!(&[(800, 600)]);
+    let _hadlock = Hadlock::start(&xvfb, CONFIG);
+    let client = Client::connect(&xvfb);
+
+    let first = client.map_new_window(0, 0, 100, 100);
+    let second = client.map_new_window(0, 0, 100, 100);
+    wait_for(|| Some(()).filter(|_| client.viewable(first) && client.viewable(second)))
+        .expect("windows not mapped");
+    focus(&client, second);
+    client.press_key(keysym::XK_l, xlib::Mod4Mask | xlib::ShiftMask);
+    wait_for(|| Some(()).filter(|_| !overlap(client.geometry(first), client.geometry(second))))
+        .expect("windows were not tiled");
+    let tiled_width = client.geometry(first).2;
+
+    focus(&client, first);
+    client.unmap(second);
+    wait_for(|| Some(()).filter(|_| !client.client_list().contains(&second)))
+        .expect("unmapped window still in _NET_CLIENT_LIST");
+    wait_for(|| Some(()).filter(|_| client.geometry(first).2 > tiled_width))
+        .expect("the other window did not take the space");
+
+    // Mapping it again makes it a client again
+    client.map(second);
+    wait_for(|| Some(()).filter(|_| client.viewable(second) && client.client_list().contains(&second)))
+        .expect("remapped window was not managed");
+}
+
+#[test]
+fn windows_mapped_before_start_are_adopted() {
+    let xvfb = xvfb!(&[(800, 600)]);
+    let client = Client::connect(&xvfb);
+    let shown = client.map_new_window(0, 0, 100, 100);
+    let elsewhere = client.map_new_window(0, 0, 100, 100);
+    client.set_cardinal(elsewhere, "_NET_WM_DESKTOP", &[1]);
+    assert!(client.viewable(shown) && client.viewable(elsewhere));
+
+    let _hadlock = Hadlock::start(&xvfb, CONFIG);
+    wait_for(|| {
+        let clients = client.client_list();
+        Some(()).filter(|_| clients.contains(&shown) && clients.contains(&elsewhere))
+    })
+    .expect("existing windows were not managed");
+    wait_for(|| Some(()).filter(|_| !client.viewable(elsewhere)))
+        .expect("window stayed off its _NET_WM_DESKTOP");
+    assert!(client.viewable(shown));
+    assert_eq!(client.current_desktop(), Some(0));
+
+    client.send_message(client.root, "_NET_CURRENT_DESKTOP", &[1, 0]);
+    wait_for(|| Some(()).filter(|_| client.viewable(elsewhere) && !client.viewable(shown)))
+        .expect("adopted window not on desktop 1");
+}
+
+#[test]
+fn switching_workspace_maps_only_its_windows() {
+    let xvfb = xvfb!(&[(800, 600)]);
+    let _hadlock = Hadlock::start(&xvfb, CONFIG);
+    let client = Client::connect(&xvfb);
+
+    let first = client.map_new_window(0, 0, 100, 100);
+    wait_for(|| Some(()).filter(|_| client.viewable(first))).expect("first window not mapped");
+    assert_eq!(client.current_desktop(), Some(0));
+
+    client.send_message(client.root, "_NET_CURRENT_DESKTOP", &[1, 0]);
+    wait_for(|| Some(()).filter(|_| client.current_desktop() == Some(1)))
+        .expect("desktop did not switch");
+    wait_for(|| Some(()).filter(|_| !client.viewable(first)))
+        .expect("window of the old desktop still mapped");
+
+    let second = client.map_new_window(0, 0, 100, 100);
+    wait_for(|| Some(()).filter(|_| client.viewable(second))).expect("second window not mapped");
+
+    client.send_message(client.root, "_NET_CURRENT_DESKTOP", &[0, 0]);
+    wait_for(|| {
+        Some(()).filter(|_| {
+            client.current_desktop() == Some(0)
+                && client.viewable(first)
+                && !client.viewable(second)
+        })
+    })
+    .expect("switching back did not swap the mapped windows");
+
+    let clients = client.client_list();
+    assert!(clients.contains(&first) && clients.contains(&second));
+}
+
+#[test]
+fn window_moves_to_another_desktop() {
+    let xvfb = xvfb!(&[(800, 600)]);
+    let _hadlock = Hadlock::start(&xvfb, CONFIG);
+    let client = Client::connect(&xvfb);
+
+    let w = client.map_new_window(0, 0, 100, 100);
+    wait_for(|| Some(()).filter(|_| client.viewable(w))).expect("window not mapped");
+    focus(&client, w);
+
+    client.send_message(w, "_NET_WM_DESKTOP", &[2, 2]);
+    wait_for(|| Some(()).filter(|_| !client.viewable(w)))
+        .expect("window stayed on the current desktop");
+    assert_eq!(client.current_desktop(), Some(0));
+
+    client.send_message(client.root, "_NET_CURRENT_DESKTOP", &[2, 0]);
+    wait_for(|| Some(()).filter(|_| client.viewable(w))).expect("window not on desktop 2");
+    assert!(client.client_list().contains(&w));
+    assert_eq!(client.geometry(w), (80, 120, 640, 360));
+}
+
+#[test]
+fn circulating_layout_tiles_windows() {
+    let xvfb = xvfb!(&[(800, 600)]);
+    let _hadlock = Hadlock::start(&xvfb, CONFIG);
+    let client = Client::connect(&xvfb);
+
+    let first = client.map_new_window(0, 0, 100, 100);
+    let second = client.map_new_window(0, 0, 100, 100);
+    wait_for(|| Some(()).filter(|_| client.viewable(first) && client.viewable(second)))
+        .expect("windows not mapped");
+    assert!(overlap(client.geometry(first), client.geometry(second)));
+    focus(&client, second);
+
+    client.press_key(keysym::XK_l, xlib::Mod4Mask | xlib::ShiftMask);
+    wait_for(|| Some(()).filter(|_| !overlap(client.geometry(first), client.geometry(second))))
+        .expect("windows were not tiled");
+    for w in [first, second].iter() {
+        let (x, y, width, height) = client.geometry(*w);
+        assert!(x >= 0 && x + width as i32 <= 800);
+        assert!(y >= 0 && y + height as i32 <= 600);
+    }
+}
+
+#[test]
+fn windows_open_on_the_monitor_with_the_pointer() {
+    let xvfb = xvfb!(&[(800, 600), (640, 480)]);
+    let _hadlock = Hadlock::start(&xvfb, CONFIG);
+    let client = Client::connect(&xvfb);
+
+    let left = client.map_new_window(0, 0, 100, 100);
+    wait_for(|| Some(()).filter(|_| client.viewable(left))).expect("window not mapped");
+    assert!(client.geometry(left).0 < 800);
+
+    // Every monitor starts out on a workspace of its own
+    client.warp_pointer(1000, 200);
+    wait_for(|| Some(()).filter(|_| client.current_desktop() == Some(1)))
+        .expect("pointer did not change monitor");
+
+    let right = client.map_new_window(0, 0, 100, 100);
+    wait_for(|| Some(()).filter(|_| client.viewable(right))).expect("window not mapped");
+    assert_eq!(client.geometry(right), (864, 96, 512, 288));
+    assert!(client.viewable(left));
+
+    let clients = client.client_list();
+    assert!(clients.contains(&left) && clients.contains(&right));
+}
+
+#[test]
+fn replace_takes_over_from_the_running_hadlock() {
+    let xvfb = xvfb!(&[(800, 600)]);
+    let mut first = Hadlock::start(&xvfb, CONFIG);
+    let client = Client::connect(&xvfb);
+    let w = client.map_new_window(0, 0, 100, 100);
+    wait_for(|| Some(()).filter(|_| client.client_list().contains(&w))).expect("window not managed");
+
+    let mut refused = Hadlock::spawn(&xvfb, CONFIG, &[]);
+    assert_eq!(refused.wait_exit(), Some(false));
+
+    let _second = Hadlock::spawn(&xvfb, CONFIG, &["--replace"]);
+    assert_eq!(first.wait_exit(), Some(true));
+    wait_for(|| Some(()).filter(|_| client.viewable(w) && client.client_list().contains(&w)))
+        .expect("window not adopted by the new hadlock");
+}